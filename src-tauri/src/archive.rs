@@ -0,0 +1,125 @@
+use std::{
+    fs::{self, File},
+    io::{Read, Write},
+    path::Path,
+};
+
+use chrono::Local;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use zip::{write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::customization::{import_character_json, import_new_character_json, CharacterRef};
+use crate::database::Database;
+use crate::index::is_character_file;
+use crate::settings::characters_dir;
+
+/// Name of the manifest stored at the root of every character archive.
+const MANIFEST_NAME: &str = "manifest.json";
+
+/// Describes the content of a character archive.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub app_version: String,
+    pub created_at: String,
+    pub characters: Vec<String>,
+}
+
+/// Bundles every character file of the characters directory into a zip archive at
+/// `destination`, together with a `manifest.json` listing them.
+///
+/// # Returns
+///
+/// The manifest written into the archive, or an `std::io::Error` if the characters could not be
+/// read or the archive could not be written.
+pub async fn export_all_characters(destination: &Path) -> Result<Manifest, std::io::Error> {
+    info!("Exporting all Characters to {}", destination.display());
+    let mut zip = ZipWriter::new(File::create(destination)?);
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let mut characters: Vec<String> = vec![];
+    let dir = characters_dir();
+    if Path::new(&dir).exists() {
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if !is_character_file(&path) {
+                continue;
+            }
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+
+            let mut buffer: Vec<u8> = vec![];
+            File::open(&path)?.read_to_end(&mut buffer)?;
+            zip.start_file(format!("characters/{}", file_name), options)?;
+            zip.write_all(&buffer)?;
+            characters.push(file_name);
+        }
+    }
+    characters.sort();
+
+    let manifest = Manifest {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: Local::now().to_rfc3339(),
+        characters,
+    };
+    zip.start_file(MANIFEST_NAME, options)?;
+    serde_json::to_writer_pretty(&mut zip, &manifest)?;
+    zip.finish()?;
+
+    Ok(manifest)
+}
+
+/// Outcome of importing a single character file from an archive.
+#[derive(Debug, Serialize)]
+pub struct ArchiveImportResult {
+    file_name: String,
+    pub imported: Option<CharacterRef>,
+    skipped: bool,
+    error: Option<String>,
+}
+
+/// Imports every character file found in the zip archive at `source`.
+///
+/// Each file is validated against the database `db` on its own, so a broken file
+/// doesn't prevent the others from being imported. Characters that already exist are renamed if
+/// `rename_duplicates` is set and skipped otherwise.
+///
+/// # Returns
+///
+/// One result per character file in the archive, or an `std::io::Error` if the archive itself
+/// could not be opened.
+pub async fn import_archive(source: &Path, db: &Database, rename_duplicates: bool) -> Result<Vec<ArchiveImportResult>, std::io::Error> {
+    info!("Importing Characters from {}", source.display());
+    let mut zip = ZipArchive::new(File::open(source)?)?;
+
+    let mut results: Vec<ArchiveImportResult> = vec![];
+    for index in 0..zip.len() {
+        let mut entry = zip.by_index(index)?;
+        let file_name = entry.name().to_string();
+        if entry.is_dir() || !is_character_file(Path::new(&file_name)) || file_name == MANIFEST_NAME {
+            continue;
+        }
+
+        let mut buffer: String = String::new();
+        let imported = entry.read_to_string(&mut buffer)
+            .and_then(|_| Ok(serde_json::from_str::<Value>(&buffer)?))
+            .and_then(|json| match rename_duplicates {
+                true => import_character_json(json, db).map(Some),
+                false => import_new_character_json(json, db),
+            });
+
+        results.push(match imported {
+            Ok(imported) => ArchiveImportResult {
+                file_name,
+                skipped: imported.is_none(),
+                imported,
+                error: None,
+            },
+            Err(e) => {
+                warn!("Could not import {} due to {:#?}", file_name, e);
+                ArchiveImportResult { file_name, imported: None, skipped: false, error: Some(e.to_string()) }
+            },
+        });
+    }
+    Ok(results)
+}
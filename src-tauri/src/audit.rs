@@ -0,0 +1,96 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, ErrorKind, Write},
+};
+
+use chrono::Local;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::customization::UPDATED_AT_FIELD;
+use crate::paths::data_path;
+
+const AUDIT_DIR: &str = "audit";
+
+/// A field of a character changed by the tool, as recorded in its audit log.
+///
+/// `old` is `null` for fields added by the change and `new` is `null` for fields it removed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Change {
+    field: String,
+    old: Value,
+    new: Value,
+    changed_at: String,
+}
+
+/// Returns the audit log of the character `character_id`, one JSON change per line.
+fn audit_path(character_id: &str) -> String {
+    format!("{}/{}.log", data_path(AUDIT_DIR), character_id)
+}
+
+/// Appends every field that differs between `previous` and `current`, the content of the
+/// character file of `character_id` before and after a save, to its audit log.
+///
+/// Unlike versions, the audit log is never trimmed, so it covers everything the tool altered.
+pub fn record_changes(character_id: &str, previous: Option<&Value>, current: &Value) -> Result<(), std::io::Error> {
+    if character_id.is_empty() {
+        return Ok(());
+    }
+    let empty = serde_json::Map::new();
+    let previous = previous.and_then(Value::as_object).unwrap_or(&empty);
+    let current = current.as_object().unwrap_or(&empty);
+    let changed_at = Local::now().to_rfc3339();
+
+    let mut changes: Vec<Change> = vec![];
+    for (field, new) in current {
+        if field != UPDATED_AT_FIELD && previous.get(field) != Some(new) {
+            let old = previous.get(field).cloned().unwrap_or(Value::Null);
+            changes.push(Change { field: field.clone(), old, new: new.clone(), changed_at: changed_at.clone() });
+        }
+    }
+    for (field, old) in previous {
+        if !current.contains_key(field) {
+            changes.push(Change { field: field.clone(), old: old.clone(), new: Value::Null, changed_at: changed_at.clone() });
+        }
+    }
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    fs::create_dir_all(data_path(AUDIT_DIR))?;
+    let file = OpenOptions::new().create(true).append(true).open(audit_path(character_id))?;
+    let mut writer = BufWriter::new(file);
+    for change in &changes {
+        serde_json::to_writer(&mut writer, change)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
+
+/// Reads every change recorded for the character `character_id`, oldest first.
+///
+/// Lines that can't be parsed, e.g. one cut short by a crash, are logged and skipped. The log is
+/// kept after the character is deleted, so it can still be read by its id.
+pub fn get_change_history(character_id: &str) -> Result<Vec<Change>, std::io::Error> {
+    if uuid::Uuid::parse_str(character_id).is_err() {
+        return Err(std::io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("{} is not a Character id", character_id),
+        ));
+    }
+    let file = match File::open(audit_path(character_id)) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+
+    let mut changes: Vec<Change> = vec![];
+    for line in BufReader::new(file).lines() {
+        match serde_json::from_str(&line?) {
+            Ok(change) => changes.push(change),
+            Err(e) => warn!("Skipping unreadable change of {} due to {:#?}", character_id, e),
+        }
+    }
+    Ok(changes)
+}
@@ -0,0 +1,107 @@
+use std::{
+    fs,
+    path::Path,
+};
+
+use chrono::{Local, NaiveDateTime};
+use log::{info, warn};
+use serde::Serialize;
+
+use crate::paths::data_path;
+use crate::settings::get_settings;
+
+const BACKUP_DIR: &str = "backups";
+const TIMESTAMP_FORMAT: &str = "%Y%m%d%H%M%S%3f";
+
+/// Returns the backups directory, inside the data directory.
+fn backup_dir() -> String {
+    data_path(BACKUP_DIR)
+}
+
+#[derive(Debug, Serialize)]
+pub struct Backup {
+    file_name: String,
+    created_at: String,
+}
+
+/// Splits a backup file name in the character id and its timestamp.
+fn parse_backup_name(file_name: &str) -> Option<(&str, NaiveDateTime)> {
+    let (id, timestamp) = file_name.strip_suffix(".json")?.rsplit_once('_')?;
+    let created_at = NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT).ok()?;
+    Some((id, created_at))
+}
+
+/// Returns the file names of every backup of the character `id`, oldest first.
+fn backups_of(id: &str) -> Result<Vec<String>, std::io::Error> {
+    if !Path::new(&backup_dir()).exists() {
+        return Ok(vec![]);
+    }
+
+    let mut backups: Vec<String> = vec![];
+    for entry in fs::read_dir(backup_dir())? {
+        let file_name = entry?.file_name().to_string_lossy().into_owned();
+        if matches!(parse_backup_name(&file_name), Some((backup_id, _)) if backup_id == id) {
+            backups.push(file_name);
+        }
+    }
+    backups.sort();
+    Ok(backups)
+}
+
+/// Copies the character file at `file_path`, belonging to the character `id`, into the backups
+/// folder, if it exists.
+///
+/// The backup is named after the character id with the current timestamp appended, e.g.
+/// `backups/{id}_20231224153000123.json`, so backups survive renaming the character. Once the
+/// copy is done only the last `max_backups` backups of that character are kept. Nothing is
+/// copied if backups are disabled in the settings.
+pub fn backup_character_file(file_path: &str, id: &str) -> Result<(), std::io::Error> {
+    let settings = get_settings().backup;
+    let path = Path::new(file_path);
+    if !settings.enabled || !path.is_file() || id.is_empty() {
+        return Ok(());
+    }
+
+    fs::create_dir_all(backup_dir())?;
+
+    let backup_name = format!("{}_{}.json", id, Local::now().format(TIMESTAMP_FORMAT));
+    fs::copy(path, Path::new(&backup_dir()).join(&backup_name))?;
+    info!("Backed up {} as {}", file_path, backup_name);
+
+    let backups = backups_of(id)?;
+    if backups.len() > settings.max_backups {
+        for old in &backups[..backups.len() - settings.max_backups] {
+            if let Err(e) = fs::remove_file(Path::new(&backup_dir()).join(old)) {
+                warn!("Could not remove old backup {} due to {:#?}", old, e);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Lists the backups available for the character `id`, newest first.
+pub fn list_backups(id: &str) -> Result<Vec<Backup>, std::io::Error> {
+    let mut backups: Vec<Backup> = vec![];
+    for file_name in backups_of(id)?.into_iter().rev() {
+        if let Some((_, created_at)) = parse_backup_name(&file_name) {
+            backups.push(Backup {
+                created_at: created_at.format("%d-%m-%Y %H:%M:%S").to_string(),
+                file_name,
+            });
+        }
+    }
+    Ok(backups)
+}
+
+/// Returns the path of the backup named `file_name`, making sure it belongs to the character.
+pub fn backup_path(id: &str, file_name: &str) -> Result<String, std::io::Error> {
+    match parse_backup_name(file_name) {
+        Some((backup_id, _)) if backup_id == id && !file_name.contains(['/', '\\']) => {
+            Ok(format!("{}/{}", backup_dir(), file_name))
+        },
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{} is not a backup of {}", file_name, id),
+        )),
+    }
+}
@@ -0,0 +1,159 @@
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::customization::{apply_appearance, delete_character, model_of, new_character, AppearanceDelta, CharacterLocks, NewCharacter};
+use crate::database::Database;
+use crate::presets::load_preset;
+
+/// A row of a bulk creation CSV file. Every column but the name, gender and species is optional,
+/// empty cells keep the value of the template.
+#[derive(Debug, Deserialize)]
+struct BulkRow {
+    first_name: String,
+    #[serde(default)]
+    surname: String,
+    gender: String,
+    species: String,
+    hair_type: Option<String>,
+    hair_color: Option<usize>,
+    eye_color: Option<usize>,
+    skintone: Option<String>,
+    facepaint: Option<String>,
+    extras: Option<String>,
+}
+
+/// Outcome of one row of a bulk creation.
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum BulkRowOutcome {
+    Created { id: String },
+    Failed { message: String },
+}
+
+/// Outcome of one row of a bulk creation, `row` counting from 1 after the header.
+#[derive(Debug, Serialize)]
+pub struct BulkRowResult {
+    pub row: usize,
+    pub name: String,
+    pub outcome: BulkRowOutcome,
+}
+
+/// Creates one character from `row`, with the appearance it describes.
+///
+/// A character whose appearance is refused is deleted again, so only valid characters are left
+/// behind. It stays in the trash.
+async fn create_from_row(row: &BulkRow, db: &Database) -> Result<String, std::io::Error> {
+    let model = model_of(&row.gender, &row.species).ok_or_else(|| std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("There is no model for gender {} and species {}", row.gender, row.species),
+    ))?;
+    let id = match new_character(&row.first_name, &row.surname, None).await? {
+        NewCharacter::Created { id } => id,
+        NewCharacter::AlreadyExists { file_name, .. } => return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("Character file {} already exists", file_name),
+        )),
+    };
+
+    let appearance = AppearanceDelta {
+        gender_race: Some(model),
+        hair_type: row.hair_type.clone(),
+        hair_color: row.hair_color,
+        eye_color: row.eye_color,
+        skintone: row.skintone.clone(),
+        facepaint: row.facepaint.clone(),
+        extras: row.extras.clone(),
+        ..AppearanceDelta::default()
+    };
+    if let Err(e) = apply_appearance(&id, &appearance, db).await {
+        if let Err(e) = delete_character(&id).await {
+            warn!("Could not delete the invalid Character {} due to {:#?}", id, e);
+        }
+        return Err(e);
+    }
+    Ok(id)
+}
+
+/// Creates a character for every row of the CSV file at `csv_path`, e.g. to seed a test server.
+///
+/// The file starts with a header naming the columns `first_name`, `surname`, `gender`,
+/// `species`, `hair_type`, `hair_color`, `eye_color`, `skintone`, `facepaint` and `extras`.
+/// Rows are created independently, a row that fails is reported and doesn't stop the others.
+///
+/// # Returns
+///
+/// The outcome of every row, or an `std::io::Error` if the file can't be read at all.
+pub async fn bulk_create(csv_path: &str, db: &Database) -> Result<Vec<BulkRowResult>, std::io::Error> {
+    info!("Creating Characters from {}", csv_path);
+    let mut reader = csv::Reader::from_path(csv_path)?;
+
+    let mut results: Vec<BulkRowResult> = vec![];
+    for (index, row) in reader.deserialize::<BulkRow>().enumerate() {
+        let (name, outcome) = match row {
+            Ok(row) => {
+                let name = format!("{} {}", row.first_name, row.surname).trim().to_string();
+                match create_from_row(&row, db).await {
+                    Ok(id) => (name, BulkRowOutcome::Created { id }),
+                    Err(e) => (name, BulkRowOutcome::Failed { message: e.to_string() }),
+                }
+            },
+            Err(e) => (String::new(), BulkRowOutcome::Failed { message: e.to_string() }),
+        };
+        if let BulkRowOutcome::Failed { message } = &outcome {
+            warn!("Could not create row {} of {}: {}", index + 1, csv_path, message);
+        }
+        results.push(BulkRowResult { row: index + 1, name, outcome });
+    }
+    Ok(results)
+}
+
+/// Progress of a bulk operation, reported after every character.
+#[derive(Debug, Clone, Serialize)]
+pub struct BulkProgress {
+    /// Characters handled so far, including `id`.
+    pub done: usize,
+    pub total: usize,
+    pub id: String,
+    /// Why the character `id` failed, `None` if it succeeded.
+    pub error: Option<String>,
+}
+
+/// Applies the preset `preset` to every character of `ids`, e.g. to give every test character
+/// the same appearance.
+///
+/// Every character is locked through `locks` while it is changed, and `on_progress` is called
+/// once it is done. A character that fails is reported and doesn't stop the others, e.g. one
+/// whose other cosmetics can't be combined with the preset, see `get_constraints`.
+///
+/// # Returns
+///
+/// The progress reported for every character, or an `std::io::Error` if the preset can't be
+/// loaded, in which case no character is touched.
+pub async fn apply_preset_bulk<F>(preset: &str, ids: &[String], locks: &CharacterLocks, db: &Database, on_progress: F) -> Result<Vec<BulkProgress>, std::io::Error>
+where
+    F: Fn(&BulkProgress),
+{
+    info!("Applying Preset {} to {} Characters", preset, ids.len());
+    let appearance = load_preset(preset)?;
+
+    let mut results: Vec<BulkProgress> = vec![];
+    for (index, id) in ids.iter().enumerate() {
+        let applied = {
+            let _guard = locks.lock(id).await;
+            apply_appearance(id, &appearance, db).await
+        };
+        if let Err(e) = &applied {
+            warn!("Could not apply Preset {} to Character {} due to {:#?}", preset, id, e);
+        }
+
+        let progress = BulkProgress {
+            done: index + 1,
+            total: ids.len(),
+            id: id.clone(),
+            error: applied.err().map(|e| e.to_string()),
+        };
+        on_progress(&progress);
+        results.push(progress);
+    }
+    Ok(results)
+}
@@ -1,222 +1,1999 @@
 use std::{
-    fs::File,
-    path::Path, io::Read,
+    collections::HashMap,
+    fs::{self, File},
+    future::Future,
+    path::Path, io::{Read, BufWriter, Write},
+    sync::{atomic::{AtomicU64, Ordering}, Arc, Mutex},
+    time::Duration,
 };
 
+use chrono::Local;
 use log::{info, warn, error};
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::audit::record_changes;
+use crate::backup::{backup_character_file, backup_path};
+use crate::migration::migrate_character;
+use crate::repair::salvage_fields;
+use crate::settings::{characters_dir, get_settings};
+use crate::lock::{ensure_unlocked, is_locked};
+use crate::index::{character_file, find_character_file, is_character_file, rebuild_index, remove_from_index, update_index, CharacterOrder, CharacterSummary};
+use crate::database::*;
+use crate::filename::sanitize_file_stem;
+use crate::templates::{load_fallback_template, load_template};
+use crate::trash::{trash_character_file, trashed_path};
+use crate::utils::{move_file, write_json_atomically};
+use crate::model::{model_by_id, model_for, AccessorySlot, EquipmentSlot, Gender, Species};
+use crate::validation::{check_fields, missing_later_tables, validate_character, IssueKind, ValidationIssue};
+use crate::versions::{load_version, record_version};
+use crate::watcher::expect_change;
 
+/// Field of a character file holding its id, which identifies the character independently of
+/// its name and file name.
+pub const ID_FIELD: &str = "CharacterId";
 
-/// Creates a new character file for the specified `username` if it doesn't already exist.
+/// Field of a character file holding the GUID the server knows the character by.
+pub const GUID_FIELD: &str = "PlayerGUID";
+
+/// Field of a character file the game reads the beard or the wings from, depending on the model.
+pub const EXTRAS_FIELD: &str = "HumanBeardsPixieWings";
+
+/// Field of a character file keeping its beard, also while the character is not a male human.
+pub const BEARD_FIELD: &str = "HumanBeard";
+
+/// Field of a character file keeping its wings, also while the character is not a pixie.
+pub const WINGS_FIELD: &str = "PixieWings";
+
+/// Field of a character file holding notes of the player, e.g. a build or a backstory. The server
+/// ignores it.
+pub const NOTES_FIELD: &str = "Notes";
+
+/// Field of a character file marking it as a favorite of the player.
+pub const FAVORITE_FIELD: &str = "Favorite";
+
+/// Field of a character file holding the name of the account, the server login, the character
+/// belongs to.
+pub const ACCOUNT_FIELD: &str = "Account";
+
+/// Field of a character file holding when it was created, in ISO-8601.
+pub const CREATED_AT_FIELD: &str = "CreatedAt";
+
+/// Field of a character file holding when it was last written, in ISO-8601.
+pub const UPDATED_AT_FIELD: &str = "UpdatedAt";
+
+/// Fields of a character file that make up its appearance.
+pub const APPEARANCE_FIELDS: [&str; 26] = [
+    "PlayerModel",
+    "PlayerHead",
+    "PlayerHair",
+    "HairColor",
+    "HairHighlightColor",
+    "EyeColor",
+    "EyeShape",
+    "Skintone",
+    "FacePaint",
+    "FacePaintColor",
+    "FacePaintOpacity",
+    "HumanBeardsPixieWings",
+    "HumanBeard",
+    "PixieWings",
+    "WingColor",
+    "EyebrowStyle",
+    "EyebrowColor",
+    "BodyType",
+    "BodyScale",
+    "Voice",
+    "Tail",
+    "Ears",
+    "Markings",
+    "Glasses",
+    "Mask",
+    "Earrings",
+];
+
+/// Generates a new, random character id.
+pub fn new_character_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+/// Generates a new, random `PlayerGUID`.
 ///
-/// This function checks if a character file with the given `username` exists in the "characters"
-/// directory. If it does not exist, the function creates a new character file by copying
-/// the contents from the "Fallback.json" template. The character file is stored in the
-/// "characters" directory with the naming convention "username.json". If the character file
-/// already exists, the function logs a warning and skips the creation step.
+/// GUIDs are kept below 2^53 so the frontend, which reads them as JavaScript numbers, sees the
+/// exact value. That still leaves collisions between characters practically impossible.
+pub fn new_player_guid() -> u64 {
+    rand::thread_rng().gen_range(1..1u64 << 53)
+}
+
+/// Returns the field holding the extra worn by the model of the character `json`, the beard of a
+/// male human or the wings of a pixie, or `None` for models without extras.
+fn extras_field_of(json: &Value) -> Option<&'static str> {
+    match gender_species_of(json["PlayerModel"].as_u64().unwrap_or_default()) {
+        ("m", "human") => Some(BEARD_FIELD),
+        (_, "fairy") => Some(WINGS_FIELD),
+        _ => None,
+    }
+}
+
+/// Copies the beard or the wings of the current model of `json` into `HumanBeardsPixieWings`,
+/// the field the game reads.
+fn sync_extras(json: &mut Value) {
+    let extra = extras_field_of(json).and_then(|field| json[field].as_str()).unwrap_or_default().to_string();
+    json[EXTRAS_FIELD] = serde_json::Value::String(extra);
+}
+
+/// Sets the extra worn by the current model of `json`, the beard of a male human or the wings of
+/// a pixie. The extra kept for the other species is left untouched.
+fn set_extras(json: &mut Value, extra: &str) {
+    if let Some(field) = extras_field_of(json) {
+        json[field] = serde_json::Value::String(extra.into());
+    }
+    json[EXTRAS_FIELD] = serde_json::Value::String(extra.into());
+}
+
+/// Returns the id stored in the character `json`.
+fn id_of(json: &Value) -> &str {
+    json[ID_FIELD].as_str().unwrap_or_default()
+}
+
+/// Separates the first name from the surname in character file names.
+const NAME_SEPARATOR: &str = "_";
+
+/// Returns the name of the character `first_name` `surname` as shown to the user, without a
+/// trailing space when the surname is empty.
+pub fn full_name(first_name: &str, surname: &str) -> String {
+    match surname.is_empty() {
+        true => first_name.to_string(),
+        false => format!("{} {}", first_name, surname),
+    }
+}
+
+/// Returns the file stem of the character `first_name` `surname`, e.g. `Ann_Ason`, or just
+/// `Ann` without a surname.
 ///
-/// # Arguments
+/// The name is sanitized with `sanitize_file_stem`, failing if it can't be used as a file name.
+fn character_file_stem(first_name: &str, surname: &str) -> Result<String, std::io::Error> {
+    match surname.is_empty() {
+        true => sanitize_file_stem(first_name),
+        false => sanitize_file_stem(&format!("{}{}{}", first_name, NAME_SEPARATOR, surname)),
+    }
+}
+
+/// Returns the path of the character file belonging to `first_name` and `surname`.
 ///
-/// * `username` - A string representing the username of the character.
+/// The names are separated, so `Ann Ason` and `Anna Son` get different files.
+fn character_path(first_name: &str, surname: &str) -> Result<String, std::io::Error> {
+    Ok(format!("{}/{}.json", characters_dir(), character_file_stem(first_name, surname)?))
+}
+
+/// Renames the character files named with the earlier scheme, the first name directly followed
+/// by the surname, e.g. `AnnAson.json`, to the current one.
+///
+/// Files with any other name, e.g. renamed by hand, are left alone, and so are files whose new
+/// name is already taken or that are locked by a running server.
 ///
 /// # Returns
 ///
-/// A `Result` indicating success (`Ok(())`) if the character file is created or already exists,
-/// or an `std::io::Error` in case of file-related errors during the creation process.
+/// The amount of renamed files.
+pub fn migrate_file_names() -> Result<usize, std::io::Error> {
+    let dir = characters_dir();
+    if !Path::new(&dir).exists() {
+        return Ok(0);
+    }
+
+    let mut renamed = 0;
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if !is_character_file(&path) {
+            continue;
+        }
+        let file_path = path.to_string_lossy().into_owned();
+        let json = match read_character(&file_path) {
+            Ok(json) => json,
+            Err(_) => continue,
+        };
+        let first_name = json["FirstName"].as_str().unwrap_or_default();
+        let surname = json["LastName"].as_str().unwrap_or_default();
+        let old_stem = match sanitize_file_stem(&format!("{}{}", first_name, surname)) {
+            Ok(old_stem) => old_stem,
+            Err(_) => continue,
+        };
+        let new_path = match character_path(first_name, surname) {
+            Ok(new_path) => new_path,
+            Err(_) => continue,
+        };
+        if path.file_stem().map_or(true, |stem| stem.to_string_lossy() != old_stem) || new_path == file_path {
+            continue;
+        }
+        if existing_character_file(&new_path)?.is_some() {
+            warn!("Not renaming {} to {}, the new name is already taken", file_path, new_path);
+            continue;
+        }
+        if is_locked(&file_path) {
+            warn!("Not renaming {} to {}, it is locked by a running server", file_path, new_path);
+            continue;
+        }
+
+        info!("Renaming {} to {}", file_path, new_path);
+        move_file(&file_path, &new_path)?;
+        renamed += 1;
+    }
+    if renamed > 0 {
+        rebuild_index()?;
+    }
+    Ok(renamed)
+}
+
+/// Returns the path of the character file that collides with `file_path`, if any.
+///
+/// File names are compared case-insensitively, since `Bob.json` and `bob.json` are the same file
+/// on Windows and macOS, and users share character folders between platforms.
+fn existing_character_file(file_path: &str) -> Result<Option<String>, std::io::Error> {
+    let path = Path::new(file_path);
+    if path.exists() {
+        return Ok(Some(file_path.to_string()));
+    }
+    let (directory, file_name) = match (path.parent(), path.file_name()) {
+        (Some(directory), Some(file_name)) if directory.exists() => (directory, file_name.to_string_lossy().to_lowercase()),
+        _ => return Ok(None),
+    };
+
+    for entry in fs::read_dir(directory)? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().to_lowercase() == file_name {
+            return Ok(Some(entry.path().to_string_lossy().into_owned()));
+        }
+    }
+    Ok(None)
+}
+
+/// Fails with `std::io::ErrorKind::AlreadyExists` if a character file exists at `file_path`,
+/// ignoring case.
+fn ensure_available(file_path: &str) -> Result<(), std::io::Error> {
+    if let Some(existing) = existing_character_file(file_path)? {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("Character file {} already exists", existing),
+        ));
+    }
+    Ok(())
+}
+
+/// Reads and parses the character file located at `file_path`.
+///
+/// Files written with an older format are migrated to the current one, the upgraded content is
+/// saved with the next write.
+///
+/// Keys keep the order of the file and keys this app doesn't know, e.g. added by server tools,
+/// are kept as they are, so writing the character back only changes the fields that were edited.
+///
+/// A coalesced write still waiting for its quiet period is returned instead of the file content,
+/// so successive changes build on each other.
+fn read_character(file_path: &str) -> Result<Value, std::io::Error> {
+    if let Some(pending) = PENDING_WRITES.lock().unwrap().iter().find(|pending| pending.file_path == file_path) {
+        return Ok(pending.json.clone());
+    }
+    let mut file = File::open(file_path)?;
+    let mut buffer: String = String::new();
+    file.read_to_string(&mut buffer)?;
+    let mut json = serde_json::from_str(&buffer)?;
+    migrate_character(&mut json)?;
+    Ok(json)
+}
+
+/// Writes `json` to the character file located at `file_path`.
+///
+/// The data is first serialized into a temporary file next to the target, flushed to disk and
+/// only then renamed over the original. A serialization error or a crash halfway through the
+/// write therefore leaves the previous character file untouched instead of truncating it.
 ///
-/// # Examples
+/// If a character file already exists at `file_path`, it is backed up before being replaced.
+/// Every successful write is recorded as a new version of the character and in the index.
 ///
-/// ```
-/// use your_module::new_character;
+/// `UpdatedAt` is set to the current time, and `CreatedAt` too if neither `json` nor the
+/// previous content of the file have it. A coalesced write still pending for the character is
+/// dropped, `json` already builds on it.
 ///
-/// // Assuming a valid username is provided
-/// let result = new_character("example_user");
+/// Nothing is written if the file is locked by a running server.
+fn write_character(file_path: &str, json: &Value) -> Result<(), std::io::Error> {
+    ensure_unlocked(file_path)?;
+    discard_pending_write(id_of(json));
+    let previous = read_character(file_path).ok();
+    backup_character_file(file_path, id_of(json))?;
+
+    let now = Local::now().to_rfc3339();
+    let mut json = json.clone();
+    if !json[CREATED_AT_FIELD].is_string() {
+        let created_at = previous.as_ref()
+            .and_then(|previous| previous[CREATED_AT_FIELD].as_str())
+            .map_or_else(|| now.clone(), str::to_string);
+        json[CREATED_AT_FIELD] = serde_json::Value::String(created_at);
+    }
+    json[UPDATED_AT_FIELD] = serde_json::Value::String(now);
+    let json = &json;
+
+    match write_json_atomically(file_path, json, get_settings().pretty_json) {
+        Ok(_) => {
+            info!("Operation finished successfully");
+            if let Err(e) = record_version(id_of(json), previous.as_ref(), json) {
+                warn!("Could not record a version of {} due to {:#?}", file_path, e);
+            }
+            if let Err(e) = record_changes(id_of(json), previous.as_ref(), json) {
+                warn!("Could not record the changes to {} in the audit log due to {:#?}", file_path, e);
+            }
+            if let Err(e) = update_index(file_path, json) {
+                warn!("Could not update the index with {} due to {:#?}", file_path, e);
+            }
+            Ok(())
+        },
+        Err(e) => {
+            eprintln!("Operation failed due to {:#?}", &e);
+            error!("Operation failed due to {:#?}", &e);
+            Err(e)
+        },
+    }
+}
+
+/// Quiet period a coalesced write waits for before reaching the disk.
+const WRITE_DELAY: Duration = Duration::from_millis(300);
+
+/// A coalesced write of a character waiting for its quiet period to end.
+struct PendingWrite {
+    id: String,
+    file_path: String,
+    json: Value,
+    generation: u64,
+}
+
+/// Coalesced writes not on disk yet, at most one per character.
+static PENDING_WRITES: Mutex<Vec<PendingWrite>> = Mutex::new(Vec::new());
+
+/// Source of the generation of every coalesced write, so a timer only writes the change it was
+/// started for.
+static WRITE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Writes `json` to the character file located at `file_path` once no other change was made to
+/// the character for `WRITE_DELAY`.
 ///
-/// match result {
-///     Ok(_) => {
-///         // Character file created or already exists
-///         println!("Character file for 'example_user' is ready.");
-///     },
-///     Err(error) => {
-///         // Handle the error gracefully
-///         eprintln!("Error creating character file: {:?}", error);
-///     }
-/// }
-/// ```
-pub async fn new_character(first_name: &str, surname: &str) -> Result<(), std::io::Error> {
+/// Used for changes made in quick succession, e.g. while dragging a slider, so only the last one
+/// is written, backed up and recorded as a version. Until then, `read_character` returns `json`.
+///
+/// Fails right away if the file is locked by a running server.
+fn write_character_later(file_path: &str, json: &Value) -> Result<(), std::io::Error> {
+    ensure_unlocked(file_path)?;
+    let id = id_of(json).to_string();
+    let generation = WRITE_GENERATION.fetch_add(1, Ordering::Relaxed);
+    {
+        let mut pending = PENDING_WRITES.lock().unwrap();
+        pending.retain(|pending| pending.id != id);
+        pending.push(PendingWrite { id: id.clone(), file_path: file_path.to_string(), json: json.clone(), generation });
+    }
+
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(WRITE_DELAY).await;
+        let pending = {
+            let mut pending = PENDING_WRITES.lock().unwrap();
+            match pending.iter().position(|pending| pending.id == id && pending.generation == generation) {
+                Some(index) => pending.remove(index),
+                None => return,
+            }
+        };
+        if let Err(e) = write_character(&pending.file_path, &pending.json) {
+            eprintln!("Error ocurred while writting Character {}, due to {:#?}", id, &e);
+            error!("Error ocurred while writting Character {}, due to {:#?}", id, &e);
+        }
+    });
+    Ok(())
+}
+
+/// Drops the coalesced write of the character `id`, if any.
+fn discard_pending_write(id: &str) {
+    PENDING_WRITES.lock().unwrap().retain(|pending| pending.id != id);
+}
 
-    if !Path::new("characters").exists() {
-        std::fs::create_dir_all("characters")?;
+/// Writes every coalesced write right away, e.g. before the app exits.
+pub fn flush_pending_writes() {
+    let pending = std::mem::take(&mut *PENDING_WRITES.lock().unwrap());
+    for pending in pending {
+        if let Err(e) = write_character(&pending.file_path, &pending.json) {
+            eprintln!("Error ocurred while writting Character {}, due to {:#?}", pending.id, &e);
+            error!("Error ocurred while writting Character {}, due to {:#?}", pending.id, &e);
+        }
     }
+}
 
-    match !Path::new(&format!("characters/{}.json", first_name)).is_file() {
-        true => {
-            warn!("Character File {} does not Exist, creating...", first_name);
-            let mut fallback = File::open("Fallback.json")?;
-            let mut fallback_json: String = String::new();
+/// Outcome of `new_character`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum NewCharacter {
+    /// The character file was created.
+    Created { id: String },
+    /// A character file with the same name, ignoring case, was already present and was left
+    /// untouched.
+    AlreadyExists { id: String, file_name: String },
+}
 
-            fallback.read_to_string(&mut fallback_json)?;
+/// Error of a character creation refused because every character slot is taken.
+#[derive(Debug, Clone)]
+pub struct SlotsFull {
+    pub max_characters: usize,
+}
 
-            let mut template_json:Value = serde_json::from_str(&fallback_json)?;
+impl std::fmt::Display for SlotsFull {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Every one of the {} character slots is taken", self.max_characters)
+    }
+}
 
-            let mut new_character = File::create(format!("characters/{}{}.json", first_name, surname))?;
+impl std::error::Error for SlotsFull {}
 
+/// Error returned to the frontend by the commands creating a character.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CreateCharacterError {
+    /// The characters directory already holds `max_characters` characters.
+    SlotsFull { max_characters: usize },
+    /// The character could not be created.
+    Failed { message: String },
+}
+
+impl From<std::io::Error> for CreateCharacterError {
+    fn from(e: std::io::Error) -> Self {
+        match e.get_ref().and_then(|inner| inner.downcast_ref::<SlotsFull>()) {
+            Some(full) => CreateCharacterError::SlotsFull { max_characters: full.max_characters },
+            None => CreateCharacterError::Failed { message: e.to_string() },
+        }
+    }
+}
+
+/// Creates a new character file for `first_name` `surname` if it doesn't already exist.
+///
+/// The character file is created in the characters directory by copying the contents of the
+/// template named `template` from the "templates" directory or, if no template is selected, of
+/// the "Fallback.json" template, or of the copy embedded in the binary if it is missing. It is
+/// given a freshly generated id. If a character file with that name already exists, ignoring
+/// case, the function logs a warning and skips the creation step, returning the id the index
+/// holds for that file.
+///
+/// # Returns
+///
+/// Whether the character was created or already existed, along with its id, or an
+/// `std::io::Error` in case of file-related errors during the creation process. The error wraps
+/// `SlotsFull` if the characters directory already holds the `max_characters` of the settings.
+pub async fn new_character(first_name: &str, surname: &str, template: Option<&str>) -> Result<NewCharacter, std::io::Error> {
+
+    if !Path::new(&characters_dir()).exists() {
+        std::fs::create_dir_all(characters_dir())?;
+    }
+
+    let file_path = character_path(first_name, surname)?;
+    match existing_character_file(&file_path)? {
+        None => {
+            if let Some(max_characters) = get_settings().max_characters {
+                if crate::index::list_characters()?.len() >= max_characters {
+                    warn!("Not creating {}, every one of the {} character slots is taken", file_path, max_characters);
+                    return Err(std::io::Error::new(std::io::ErrorKind::Other, SlotsFull { max_characters }));
+                }
+            }
+            warn!("Character File {} does not Exist, creating...", file_path);
+            let mut template_json:Value = load_template(template)?;
+            migrate_character(&mut template_json)?;
+
+            let id = new_character_id();
+            template_json[ID_FIELD] = serde_json::Value::String(id.clone());
+            template_json[GUID_FIELD] = serde_json::Value::Number(new_player_guid().into());
             template_json["FirstName"] = serde_json::Value::String(first_name.into());
             template_json["LastName"] = serde_json::Value::String(surname.into());
-            match serde_json::to_writer(&mut new_character, &template_json) {
-                Ok(_) => {info!("Operation finished successfully")},
-                Err(e) => {
-                    eprintln!("Operation failed due to {:#?}", &e);
-                    error!("Operation failed due to {:#?}", &e);
-        },
-            };
-            return Ok(());
+            template_json[CREATED_AT_FIELD] = serde_json::Value::String(Local::now().to_rfc3339());
+
+            write_character(&file_path, &template_json)?;
+            Ok(NewCharacter::Created { id })
         },
-        false => {
-            warn!("Character File {} is already present, skipping creation step...", first_name);
-            return Ok(());
+        Some(existing) => {
+            warn!("Character File {} is already present, skipping creation step...", existing);
+            let file_name = Path::new(&existing).file_name().unwrap_or_default().to_string_lossy().into_owned();
+            Ok(NewCharacter::AlreadyExists {
+                id: find_character_file(&file_name)?.id,
+                file_name,
+            })
         },
     }
 }
 
-pub async fn modify_gender(username: &str, surname: &str, gender: u8) -> Result<(), std::io::Error> {
+pub async fn modify_gender(id: &str, gender: u8, db: &Database) -> Result<(), std::io::Error> {
     info!("Setting GenderRace");
-    let file_path = format!("characters/{}{}.json", username, surname);
-
-    let mut file = File::open(&file_path)?;
-    let mut buffer: String = String::new();
-    file.read_to_string(&mut buffer)?;
-    let mut json: Value = serde_json::from_str(&buffer)?;
+    if model_by_id(gender.into()).is_none() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{} is not a known gender and species combination", gender),
+        ));
+    }
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
 
-    json["PlayerGUID"] = serde_json::Value::Number(gender.into());
     json["PlayerModel"] = serde_json::Value::Number(gender.into());
+    sync_extras(&mut json);
+    check_fields(&json, db, &["PlayerModel"])?;
+    clear_restricted_cosmetics(&mut json, db, &[])?;
 
-    let mut file = File::create(file_path)?;
+    write_character(&file_path, &json)
+}
 
-    match serde_json::to_writer(&mut file, &json) {
-        Ok(_) => {info!("Operation finished successfully")},
-        Err(e) => {
-            eprintln!("Operation failed due to {:#?}", &e);
-            error!("Operation failed due to {:#?}", &e);
-        },
-    };
+/// Clears the cosmetics of the character `json` that exist, but not for its current model, e.g.
+/// pixie wings left on a character that became a human.
+///
+/// Optional cosmetics are removed, while the ones every character needs, e.g. the hair or the
+/// skintone, are replaced by the first one the model offers. Markings of another model are
+/// dropped, keeping the others. Fields in `keep` are left as they are, so a value just set for
+/// the new model is checked instead of replaced.
+fn clear_restricted_cosmetics(json: &mut Value, db: &Database, keep: &[&str]) -> Result<(), std::io::Error> {
+    let (gender, species) = gender_species_of(json["PlayerModel"].as_u64().unwrap_or_default());
+    let issues = validate_character(json, db)?;
+    let mut markings_cleared = false;
+    for issue in issues {
+        if issue.kind != IssueKind::WrongModel || keep.contains(&issue.field.as_str()) {
+            continue;
+        }
+        let field = issue.field.as_str();
+        let replacement = match field {
+            EXTRAS_FIELD => {
+                info!("Removing {} {} not available for the new model", field, json[EXTRAS_FIELD]);
+                set_extras(json, "");
+                continue;
+            },
+            "Tail" | "Ears" | "EyeShape" => Some(Value::String(String::new())),
+            _ if AccessorySlot::ALL.iter().any(|slot| field == slot.field()) => Some(Value::String(String::new())),
+            "PlayerHair" => get_hairs(db, gender, None)?.first().map(|hair| Value::String(hair.addr.clone())),
+            "PlayerHead" => get_face_shapes(db, species, gender, None)?.first().map(|head| Value::String(head.addr.clone())),
+            "Skintone" => get_skintones(db, species, None)?.first().map(|skintone| Value::String(skintone.addr.clone())),
+            "Voice" => get_voices(db, species, gender, None)?.first().map(|voice| Value::Number(voice.id.into())),
+            "EyebrowStyle" => Some(Value::String(
+                get_eyebrows(db, gender, None)?.first().map(|eyebrows| eyebrows.addr.clone()).unwrap_or_default(),
+            )),
+            "Markings" if !markings_cleared => {
+                markings_cleared = true;
+                let available = get_markings(db, species, gender, None)?;
+                let markings: Vec<Value> = json["Markings"]
+                    .as_array()
+                    .map(|markings| markings.iter().filter(|marking| {
+                        marking.as_u64().map_or(false, |marking| available.iter().any(|m| m.id as u64 == marking))
+                    }).cloned().collect())
+                    .unwrap_or_default();
+                Some(Value::Array(markings))
+            },
+            _ => continue,
+        };
+        match replacement {
+            Some(value) => {
+                info!("Replacing {} {} not available for the new model with {}", field, json[field], value);
+                json[field] = value;
+            },
+            None => warn!("Keeping {} {}, the new model has none to replace it with", field, json[field]),
+        }
+    }
     Ok(())
 }
 
-pub async fn modify_eyes(username: &str, surname: &str, eye_color: usize) -> Result<(), std::io::Error> {
-    info!("Setting Eye Color");
-    let file_path = format!("characters/{}{}.json", username, surname);
+/// Switches the character `id` to `species`, keeping its gender.
+///
+/// The model is looked up in the Species table of the database `db`, and
+/// cosmetics the new species can't wear are removed.
+pub async fn modify_species(id: &str, species: Species, db: &Database) -> Result<(), std::io::Error> {
+    info!("Setting Species");
+    let json = read_character(&character_file(id)?)?;
+    let (gender, _) = gender_species_of(json["PlayerModel"].as_u64().unwrap_or_default());
+    if gender.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Character {} has no known model to take the gender from", id),
+        ));
+    }
 
-    let mut file = File::open(&file_path)?;
-    let mut buffer: String = String::new();
-    file.read_to_string(&mut buffer)?;
-    let mut json: Value = serde_json::from_str(&buffer)?;
-    json["EyeColor"] = serde_json::Value::Number(eye_color.into());
+    let models = get_species(db, None)?;
+    let model = models
+        .iter()
+        .find(|model| model.species == species.as_str() && model.gender == gender)
+        .ok_or_else(|| std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("There is no {} model for gender {} in the database", species.as_str(), gender),
+        ))?;
+    modify_gender(id, model.model, db).await
+}
 
-    let mut file = File::create(file_path)?;
+/// Switches the character `id` to the model of `gender` and `species`.
+///
+/// Only `PlayerModel` changes, the `PlayerGUID` of the character stays the same.
+pub async fn modify_model(id: &str, gender: Gender, species: Species, db: &Database) -> Result<(), std::io::Error> {
+    modify_gender(id, model_for(gender, species).id, db).await
+}
 
-    match serde_json::to_writer(&mut file, &json) {
-        Ok(_) => {info!("Operation finished successfully")},
-        Err(e) => {
-            eprintln!("Operation failed due to {:#?}", &e);
-            error!("Operation failed due to {:#?}", &e);
-        },
-    };
-    Ok(())
+pub async fn modify_eyes(id: &str, eye_color: usize, db: &Database) -> Result<(), std::io::Error> {
+    info!("Setting Eye Color");
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
+    json["EyeColor"] = serde_json::Value::Number(eye_color.into());
+    check_fields(&json, db, &["EyeColor"])?;
+
+    write_character(&file_path, &json)
 }
 
-pub async fn modify_hair(username: &str, surname: &str, hair_type: &str, haircolor: usize) -> Result<(), std::io::Error> {
+pub async fn modify_hair(id: &str, hair_type: &str, haircolor: usize, db: &Database) -> Result<(), std::io::Error> {
     info!("Setting Hair");
-    let file_path = format!("characters/{}{}.json", username, surname);
-
-    let mut file = File::open(&file_path)?;
-    let mut buffer: String = String::new();
-    file.read_to_string(&mut buffer)?;
-    let mut json: Value = serde_json::from_str(&buffer)?;
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
 
     let hair_format: String = format!("{}", hair_type);
 
     json["PlayerHair"] = serde_json::Value::String(hair_format);
     json["HairColor"] = serde_json::Value::Number(haircolor.into());
+    check_fields(&json, db, &["PlayerHair", "HairColor"])?;
 
-    let mut file = File::create(file_path)?;
-    match serde_json::to_writer(&mut file, &json) {
-        Ok(_) => {info!("Operation finished successfully")},
-        Err(e) => {
-            eprintln!("Operation failed due to {:#?}", &e);
-            error!("Operation failed due to {:#?}", &e);
-        },
-    };
-
-    Ok(())
+    write_character(&file_path, &json)
 }
 
-pub async fn modify_skintone(username: &str, surname: &str, new_skintone: &str) -> Result<(), std::io::Error> {
+pub async fn modify_skintone(id: &str, new_skintone: &str, db: &Database) -> Result<(), std::io::Error> {
     info!("Setting Skintone");
-    let file_path = format!("characters/{}{}.json", username, surname);
-
-    let mut file = File::open(&file_path)?;
-    let mut buffer: String = String::new();
-    file.read_to_string(&mut buffer)?;
-    let mut json: Value = serde_json::from_str(&buffer)?;
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
 
     json["Skintone"] = serde_json::Value::String(new_skintone.to_string());
+    check_fields(&json, db, &["Skintone"])?;
 
-    let mut file = File::create(file_path)?;
-    match serde_json::to_writer(&mut file, &json) {
-        Ok(_) => {info!("Operation finished successfully")},
-        Err(e) => {
-            eprintln!("Operation failed due to {:#?}", &e);
-            error!("Operation failed due to {:#?}", &e);
+    write_character(&file_path, &json)
+}
+
+/// Sets the extra worn by the current model of the character `id`, its beard if it is a male
+/// human or its wings if it is a pixie.
+pub async fn modify_extras(id: &str, extra: &str, db: &Database) -> Result<(), std::io::Error> {
+    info!("Setting Extras");
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
+
+    set_extras(&mut json, extra);
+    check_fields(&json, db, &[EXTRAS_FIELD])?;
+
+    write_character(&file_path, &json)
+}
+
+/// Sets the eyebrows of the character `id` to the eyebrow `style` in `color`, one of the hair
+/// colors.
+pub async fn modify_eyebrows(id: &str, style: &str, color: usize, db: &Database) -> Result<(), std::io::Error> {
+    info!("Setting Eyebrows");
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
+
+    json["EyebrowStyle"] = serde_json::Value::String(style.into());
+    json["EyebrowColor"] = serde_json::Value::Number(color.into());
+    check_fields(&json, db, &["EyebrowStyle", "EyebrowColor"])?;
+
+    write_character(&file_path, &json)
+}
+
+/// Sets the head of the character `id` to `face_shape`, one of the face shapes of its model.
+pub async fn modify_face_shape(id: &str, face_shape: &str, db: &Database) -> Result<(), std::io::Error> {
+    info!("Setting Face Shape");
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
+
+    json["PlayerHead"] = serde_json::Value::String(face_shape.into());
+    check_fields(&json, db, &["PlayerHead"])?;
+
+    write_character(&file_path, &json)
+}
+
+/// Returns `scale` clamped into the range the BodyTypes table allows for the species of the
+/// character `json`.
+fn body_scale_value(json: &Value, db: &Database, scale: f64) -> Result<Value, std::io::Error> {
+    let (_, species) = gender_species_of(json["PlayerModel"].as_u64().unwrap_or_default());
+    let ranges = get_body_ranges(db)?;
+    let range = ranges.iter().find(|range| range.species == species).ok_or_else(|| std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        format!("There is no body range for species {} in the database", species),
+    ))?;
+    let clamped = scale.clamp(range.min_scale, range.max_scale);
+    if clamped != scale {
+        warn!("Clamping scale {} to {}", scale, clamped);
+    }
+    float_value("scale", clamped)
+}
+
+/// Sets the body type and the scale of the character `id`.
+///
+/// `scale` is clamped into the range the BodyTypes table allows for the species of the
+/// character, while a `body_type` outside of its range is refused. The scale is set with a
+/// slider, so the write is coalesced with the changes following it.
+pub async fn modify_body(id: &str, body_type: u8, scale: f64, db: &Database) -> Result<(), std::io::Error> {
+    info!("Setting Body");
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
+
+    json["BodyType"] = serde_json::Value::Number(body_type.into());
+    json["BodyScale"] = body_scale_value(&json, db, scale)?;
+    check_fields(&json, db, &["BodyType", "BodyScale"])?;
+
+    write_character_later(&file_path, &json)
+}
+
+/// Sets the voice of the character `id` to the voice `voice_id`, one of the voices of its model.
+pub async fn modify_voice(id: &str, voice_id: usize, db: &Database) -> Result<(), std::io::Error> {
+    info!("Setting Voice");
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
+
+    json["Voice"] = serde_json::Value::Number(voice_id.into());
+    check_fields(&json, db, &["Voice"])?;
+
+    write_character(&file_path, &json)
+}
+
+/// Makes `title_id` the title displayed by the character `id`, or removes its title if
+/// `title_id` is 0.
+///
+/// The title is added to the titles the character owns if needed, so the server accepts it.
+pub async fn modify_title(id: &str, title_id: u64, db: &Database) -> Result<(), std::io::Error> {
+    info!("Setting Title");
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
+
+    if title_id != 0 {
+        let titles = get_titles(db, None)?;
+        let title = titles.iter().find(|title| title.id == title_id).ok_or_else(|| std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{} is not a known title", title_id),
+        ))?;
+        if !json["PlayerTitleStruct"]["PlayerTitles"].is_array() {
+            json["PlayerTitleStruct"]["PlayerTitles"] = Value::Array(vec![]);
+        }
+        if let Some(owned) = json["PlayerTitleStruct"]["PlayerTitles"].as_array_mut() {
+            if !owned.iter().any(|owned| owned["Id"].as_u64() == Some(title_id)) {
+                owned.push(serde_json::json!({
+                    "Id": title.id,
+                    "Place": title.place,
+                    "TitleName": title.title_name,
+                    "Unknown4": 0,
+                }));
+            }
+        }
+    }
+    json["PlayerTitleStruct"]["Title"] = serde_json::Value::Number(title_id.into());
+    check_fields(&json, db, &["PlayerTitleStruct"])?;
+
+    write_character(&file_path, &json)
+}
+
+/// Makes `class_id` the class the character `id` starts as, e.g. Ninja or Brawler.
+///
+/// The server needs a job profile in `ClientPcProfiles` for the class, so the profile of the
+/// fallback template is added if the character lacks it.
+pub async fn modify_class(id: &str, class_id: u64, db: &Database) -> Result<(), std::io::Error> {
+    info!("Setting Class");
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
+
+    let has_profile = |json: &Value| json["ClientPcProfiles"]
+        .as_array()
+        .map_or(false, |profiles| profiles.iter().any(|profile| profile["JobGUID"].as_u64() == Some(class_id)));
+    if !has_profile(&json) {
+        let template = load_fallback_template()?;
+        let profile = template["ClientPcProfiles"]
+            .as_array()
+            .and_then(|profiles| profiles.iter().find(|profile| profile["JobGUID"].as_u64() == Some(class_id)))
+            .cloned()
+            .ok_or_else(|| std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("There is no job profile for class {}", class_id),
+            ))?;
+        if !json["ClientPcProfiles"].is_array() {
+            json["ClientPcProfiles"] = Value::Array(vec![]);
+        }
+        if let Some(profiles) = json["ClientPcProfiles"].as_array_mut() {
+            profiles.push(profile);
+        }
+    }
+    json["Class"] = serde_json::Value::Number(class_id.into());
+    check_fields(&json, db, &["Class"])?;
+
+    write_character(&file_path, &json)
+}
+
+/// Equips `item_guid` in `slot` of the job profile of the current class of the character `id`,
+/// or empties the slot if `item_guid` is 0.
+///
+/// The item must be listed for the slot in the Equipment table and be part of the items the
+/// character owns.
+pub async fn modify_equipment(id: &str, slot: EquipmentSlot, item_guid: u64, db: &Database) -> Result<(), std::io::Error> {
+    info!("Setting Equipment {:?}", slot);
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
+
+    if item_guid != 0 {
+        let items = get_equipment(db, slot.id(), None)?;
+        if !items.iter().any(|item| item.guid == item_guid) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Item {} can't be equipped as {:?}", item_guid, slot),
+            ));
+        }
+        let owned = json["ClientItems"]
+            .as_array()
+            .map_or(false, |items| items.iter().any(|item| item["Guid"].as_u64() == Some(item_guid)));
+        if !owned {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Character {} doesn't own item {}", id, item_guid),
+            ));
+        }
+    }
+
+    let class = json["Class"].as_u64().unwrap_or(1);
+    let profile = json["ClientPcProfiles"]
+        .as_array_mut()
+        .and_then(|profiles| profiles.iter_mut().find(|profile| profile["JobGUID"].as_u64() == Some(class)))
+        .ok_or_else(|| std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("Character {} has no job profile for class {}", id, class),
+        ))?;
+    if !profile["Items"].is_array() {
+        profile["Items"] = Value::Array(vec![]);
+    }
+    if let Some(equipped) = profile["Items"].as_array_mut() {
+        match equipped.iter_mut().find(|equipped| equipped["Item1"].as_u64() == Some(slot.id().into())) {
+            Some(equipped) => equipped["Item2"]["ItemGUID"] = serde_json::Value::Number(item_guid.into()),
+            None => equipped.push(serde_json::json!({
+                "Item1": slot.id(),
+                "Item2": { "ItemGUID": item_guid, "Category": slot.id() },
+            })),
+        }
+    }
+
+    write_character(&file_path, &json)
+}
+
+/// Sets the eye shape of the character `id`, one of the eye shapes of its species. An empty
+/// `eye_shape` keeps the eyes of the model.
+pub async fn modify_eye_shape(id: &str, eye_shape: &str, db: &Database) -> Result<(), std::io::Error> {
+    info!("Setting Eye Shape");
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
+
+    json["EyeShape"] = serde_json::Value::String(eye_shape.into());
+    check_fields(&json, db, &["EyeShape"])?;
+
+    write_character(&file_path, &json)
+}
+
+/// Sets the accessory the character `id` wears in `slot`, one of the accessories of its model.
+/// An empty `item` removes it.
+pub async fn modify_accessory(id: &str, slot: AccessorySlot, item: &str, db: &Database) -> Result<(), std::io::Error> {
+    info!("Setting Accessory {}", slot.as_str());
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
+
+    json[slot.field()] = serde_json::Value::String(item.into());
+    check_fields(&json, db, &[slot.field()])?;
+
+    write_character(&file_path, &json)
+}
+
+/// Sets the tail of the character `id`, one of the tails of its species. An empty `tail`
+/// removes it.
+pub async fn modify_tail(id: &str, tail: &str, db: &Database) -> Result<(), std::io::Error> {
+    info!("Setting Tail");
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
+
+    json["Tail"] = serde_json::Value::String(tail.into());
+    check_fields(&json, db, &["Tail"])?;
+
+    write_character(&file_path, &json)
+}
+
+/// Sets the ears of the character `id`, one of the ear styles of its model. An empty `ears`
+/// keeps the ears of the model.
+pub async fn modify_ears(id: &str, ears: &str, db: &Database) -> Result<(), std::io::Error> {
+    info!("Setting Ears");
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
+
+    json["Ears"] = serde_json::Value::String(ears.into());
+    check_fields(&json, db, &["Ears"])?;
+
+    write_character(&file_path, &json)
+}
+
+/// Returns `marking_ids` without the repeated ids, in the order they first appear.
+fn markings_value(marking_ids: &[usize]) -> Value {
+    let mut markings: Vec<usize> = vec![];
+    for marking in marking_ids {
+        if !markings.contains(marking) {
+            markings.push(*marking);
+        }
+    }
+    serde_json::Value::from(markings)
+}
+
+/// Sets the tattoos and body markings of the character `id` to `marking_ids`, all worn at once.
+///
+/// Repeated ids are only written once, in the order they first appear.
+pub async fn modify_markings(id: &str, marking_ids: &[usize], db: &Database) -> Result<(), std::io::Error> {
+    info!("Setting Markings");
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
+
+    json["Markings"] = markings_value(marking_ids);
+    check_fields(&json, db, &["Markings"])?;
+
+    write_character(&file_path, &json)
+}
+
+/// Gives the hair of the character `id` highlights in `color`, from the highlight palette, or
+/// makes it single colored again if `color` is `None`.
+pub async fn modify_hair_highlights(id: &str, color: Option<usize>, db: &Database) -> Result<(), std::io::Error> {
+    info!("Setting Hair Highlights");
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
+
+    match color {
+        Some(color) => json["HairHighlightColor"] = serde_json::Value::Number(color.into()),
+        None => {
+            if let Some(character) = json.as_object_mut() {
+                character.retain(|key, _| key != "HairHighlightColor");
+            }
         },
-    };
-    Ok(())
+    }
+    check_fields(&json, db, &["HairHighlightColor"])?;
+
+    write_character(&file_path, &json)
+}
+
+/// Sets the facial hair of the character `id`, worn whenever it is a male human. An empty
+/// `beard` leaves the character clean shaved.
+pub async fn modify_facial_hair(id: &str, beard: &str, db: &Database) -> Result<(), std::io::Error> {
+    info!("Setting Facial Hair");
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
+
+    json[BEARD_FIELD] = serde_json::Value::String(beard.into());
+    sync_extras(&mut json);
+    check_fields(&json, db, &[BEARD_FIELD])?;
+
+    write_character(&file_path, &json)
+}
+
+/// Tints the wings of the character `id` with `color`, from the wing color palette.
+///
+/// The tint is kept with the wings, so it applies whenever the character is a pixie.
+pub async fn modify_wing_color(id: &str, color: u32, db: &Database) -> Result<(), std::io::Error> {
+    info!("Setting Wing Color");
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
+
+    json["WingColor"] = serde_json::Value::Number(color.into());
+    check_fields(&json, db, &["WingColor"])?;
+
+    write_character(&file_path, &json)
 }
 
-pub async fn modify_extras(username: &str, surname: &str, extra: &str) -> Result<(), std::io::Error> {
+/// Sets the wings of the character `id`, worn whenever it is a pixie.
+pub async fn modify_wings(id: &str, wings: &str, db: &Database) -> Result<(), std::io::Error> {
     info!("Setting Wings");
-    let file_path = format!("characters/{}{}.json", username, surname);
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
 
-    let mut file = File::open(&file_path)?;
-    let mut buffer: String = String::new();
-    file.read_to_string(&mut buffer)?;
-    let mut json: Value = serde_json::from_str(&buffer)?;
+    json[WINGS_FIELD] = serde_json::Value::String(wings.into());
+    sync_extras(&mut json);
+    check_fields(&json, db, &[WINGS_FIELD])?;
 
-    json["HumanBeardsPixieWings"] = serde_json::Value::String(extra.into());
+    write_character(&file_path, &json)
+}
 
-    let mut file = File::create(file_path)?;
+/// Returns `opacity` clamped between 0 and 1.
+fn opacity_value(opacity: f64) -> Result<Value, std::io::Error> {
+    let clamped = opacity.clamp(0.0, 1.0);
+    if clamped != opacity {
+        warn!("Clamping opacity {} to {}", opacity, clamped);
+    }
+    float_value("opacity", clamped)
+}
 
-    match serde_json::to_writer(&mut file, &json) {
-        Ok(_) => {info!("Operation finished successfully")},
-        Err(e) => {
-            eprintln!("Operation failed due to {:#?}", &e);
-            error!("Operation failed due to {:#?}", &e);
+/// Sets the face paint of the character `id`, tinted with `color` from the face paint palette
+/// and drawn with `opacity`, between 0 and 1.
+///
+/// A `None` tint or opacity removes it from the character, so the face paint is drawn with its
+/// own colors or fully opaque. The opacity is set with a slider, so the write is coalesced with
+/// the changes following it.
+pub async fn modify_facepaint(id: &str, facepaint: &str, color: Option<u32>, opacity: Option<f64>, db: &Database) -> Result<(), std::io::Error> {
+    info!("Setting FacePaint");
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
+
+    // Altere o valor
+    json["FacePaint"] = serde_json::Value::String(facepaint.into());
+    if let Some(color) = color {
+        json["FacePaintColor"] = serde_json::Value::Number(color.into());
+    }
+    if let Some(opacity) = opacity {
+        json["FacePaintOpacity"] = opacity_value(opacity)?;
+    }
+    if let Some(character) = json.as_object_mut() {
+        character.retain(|key, _| {
+            (key != "FacePaintColor" || color.is_some()) && (key != "FacePaintOpacity" || opacity.is_some())
+        });
+    }
+    check_fields(&json, db, &["FacePaint", "FacePaintColor", "FacePaintOpacity"])?;
+
+    write_character_later(&file_path, &json)
+}
+
+/// Sets the notes of the character `id`, or removes them if `notes` is empty.
+pub async fn modify_notes(id: &str, notes: &str) -> Result<(), std::io::Error> {
+    info!("Setting Notes");
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
+
+    if notes.is_empty() {
+        if let Some(character) = json.as_object_mut() {
+            character.retain(|key, _| key != NOTES_FIELD);
+        }
+    } else {
+        json[NOTES_FIELD] = serde_json::Value::String(notes.into());
+    }
+
+    write_character(&file_path, &json)
+}
+
+/// Assigns the character `id` to the account `account`, or removes it from its account if
+/// `account` is empty.
+pub async fn modify_account(id: &str, account: &str) -> Result<(), std::io::Error> {
+    info!("Setting Account");
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
+
+    if account.is_empty() {
+        if let Some(character) = json.as_object_mut() {
+            character.retain(|key, _| key != ACCOUNT_FIELD);
+        }
+    } else {
+        json[ACCOUNT_FIELD] = serde_json::Value::String(account.into());
+    }
+
+    write_character(&file_path, &json)
+}
+
+/// Characters belonging to one account.
+#[derive(Debug, Serialize)]
+pub struct AccountGroup {
+    /// Name of the account, `None` for the characters not assigned to any account.
+    pub account: Option<String>,
+    pub characters: Vec<CharacterSummary>,
+}
+
+/// Returns every character grouped by account, sorted by account name, with the characters
+/// without an account last.
+pub async fn list_characters_by_account() -> Result<Vec<AccountGroup>, std::io::Error> {
+    let mut groups: Vec<AccountGroup> = vec![];
+    for character in crate::index::list_characters()? {
+        match groups.iter_mut().find(|group| group.account == character.account) {
+            Some(group) => group.characters.push(character),
+            None => groups.push(AccountGroup { account: character.account.clone(), characters: vec![character] }),
+        }
+    }
+    groups.sort_by(|a, b| (a.account.is_none(), &a.account).cmp(&(b.account.is_none(), &b.account)));
+    Ok(groups)
+}
+
+/// Marks the character `id` as a favorite, or unmarks it if it already was one.
+///
+/// # Returns
+///
+/// Whether the character is a favorite now.
+pub async fn toggle_favorite(id: &str) -> Result<bool, std::io::Error> {
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
+
+    let favorite = !json[FAVORITE_FIELD].as_bool().unwrap_or_default();
+    info!("Setting Favorite to {}", favorite);
+    json[FAVORITE_FIELD] = serde_json::Value::Bool(favorite);
+
+    write_character(&file_path, &json)?;
+    Ok(favorite)
+}
+
+/// Returns `true` if the character file of the character `id` is loaded by a running server, in
+/// which case every change to it is refused.
+pub async fn character_in_use(id: &str) -> Result<bool, std::io::Error> {
+    Ok(is_locked(&character_file(id)?))
+}
+
+/// Reads the character file of the character `id` and returns its parsed content.
+pub async fn get_character(id: &str) -> Result<Value, std::io::Error> {
+    info!("Loading Character {}", id);
+    read_character(&character_file(id)?)
+}
+
+/// Checks the character `id` against the character schema and the cosmetics stored in the
+/// database `db`.
+///
+/// # Returns
+///
+/// Every problem found, empty when the character is valid.
+pub async fn validate_character_file(id: &str, db: &Database) -> Result<Vec<ValidationIssue>, std::io::Error> {
+    info!("Validating Character {}", id);
+    let json = read_character(&character_file(id)?)?;
+    validate_character(&json, db).map_err(std::io::Error::from)
+}
+
+/// Deletes the character file of the character `id`.
+///
+/// The file is moved into the trash rather than removed, so a deleted character can be brought
+/// back with `restore_trashed` until the trash is purged.
+pub async fn delete_character(id: &str) -> Result<(), std::io::Error> {
+    let file_path = character_file(id)?;
+    if !Path::new(&file_path).is_file() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("Character file {} does not exist", file_path),
+        ));
+    }
+
+    ensure_unlocked(&file_path)?;
+    warn!("Deleting Character file {}", file_path);
+    discard_pending_write(id);
+    trash_character_file(&file_path)?;
+    if let Err(e) = remove_from_index(id) {
+        warn!("Could not remove {} from the index due to {:#?}", id, e);
+    }
+    Ok(())
+}
+
+/// Repairs the character file `file_name` of the characters directory when it can't be parsed.
+///
+/// Every field that can still be read is kept, the rest is filled in from the fallback template
+/// and the character is given a new id if its own was lost. The corrupt original is kept next to
+/// the repaired file with a `.corrupt` extension. Files that parse fine are left untouched.
+///
+/// # Returns
+///
+/// The id and name of the repaired character.
+pub async fn repair_character(file_name: &str) -> Result<CharacterRef, std::io::Error> {
+    if Path::new(file_name).file_name().map_or(true, |name| name != file_name) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{} is not a character file name", file_name),
+        ));
+    }
+    let file_path = format!("{}/{}", characters_dir(), file_name);
+    let content = fs::read_to_string(&file_path)?;
+
+    let json = match serde_json::from_str::<Value>(&content) {
+        Ok(json) if json.is_object() => read_character(&file_path)?,
+        _ => {
+            warn!("Repairing corrupt Character file {}", file_path);
+            let mut fields = salvage_fields(&content);
+            info!("Salvaged {} fields from {}", fields.len(), file_path);
+            if let Value::Object(template) = load_template(None)? {
+                for (key, value) in template {
+                    fields.entry(key).or_insert(value);
+                }
+            }
+            let mut json = Value::Object(fields);
+            if id_of(&json).is_empty() || character_file(id_of(&json)).is_ok() {
+                json[ID_FIELD] = serde_json::Value::String(new_character_id());
+            }
+            migrate_character(&mut json)?;
+
+            fs::copy(&file_path, format!("{}.corrupt", file_path))?;
+            write_character(&file_path, &json)?;
+            json
         },
     };
+
+    Ok(CharacterRef {
+        id: id_of(&json).to_string(),
+        first_name: json["FirstName"].as_str().unwrap_or_default().to_string(),
+        last_name: json["LastName"].as_str().unwrap_or_default().to_string(),
+    })
+}
+
+/// Moves the trashed character file `file_name` back into the characters directory.
+///
+/// Fails with `std::io::ErrorKind::AlreadyExists` if a character with the same name was
+/// created in the meantime.
+///
+/// A character trashed before ids existed, or whose id was reused in the meantime, is given a
+/// new id.
+///
+/// # Returns
+///
+/// The id and name of the restored character.
+pub async fn restore_trashed(file_name: &str) -> Result<CharacterRef, std::io::Error> {
+    let trashed_path = trashed_path(file_name)?;
+    let mut json = read_character(&trashed_path)?;
+
+    let first_name = json["FirstName"].as_str().unwrap_or_default().to_string();
+    let last_name = json["LastName"].as_str().unwrap_or_default().to_string();
+    let file_path = character_path(&first_name, &last_name)?;
+    ensure_available(&file_path)?;
+
+    info!("Restoring {} from the trash", file_name);
+    if !Path::new(&characters_dir()).exists() {
+        fs::create_dir_all(characters_dir())?;
+    }
+    if id_of(&json).is_empty() || character_file(id_of(&json)).is_ok() {
+        json[ID_FIELD] = serde_json::Value::String(new_character_id());
+        write_json_atomically(&trashed_path, &json, get_settings().pretty_json)?;
+    }
+    move_file(&trashed_path, &file_path)?;
+    if let Err(e) = update_index(&file_path, &json) {
+        warn!("Could not update the index with {} due to {:#?}", file_path, e);
+    }
+    Ok(CharacterRef { id: id_of(&json).to_string(), first_name, last_name })
+}
+
+/// Fixes the name of the character `id`, e.g. a typo, to `first_name` `surname`.
+///
+/// Surrounding whitespace is removed, and an empty `surname` leaves the character without one.
+/// `FirstName`, `LastName` and the file name are changed together by `rename_character`. Fails
+/// with `std::io::ErrorKind::InvalidInput` if `first_name` is empty.
+pub async fn modify_name(id: &str, first_name: &str, surname: &str) -> Result<(), std::io::Error> {
+    let (first_name, surname) = (first_name.trim(), surname.trim());
+    if first_name.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "A character needs a first name",
+        ));
+    }
+    info!("Setting Name to {}", full_name(first_name, surname));
+    rename_character(id, first_name, surname).await
+}
+
+/// Renames the character `id` to `new_username` `new_surname`.
+///
+/// The character is written under its new file name with updated `FirstName`/`LastName` before
+/// the old file is removed, so an interrupted rename never loses the character. Its id is kept,
+/// so backups and versions follow the character. Renaming onto an existing character fails with
+/// `std::io::ErrorKind::AlreadyExists`.
+pub async fn rename_character(id: &str, new_username: &str, new_surname: &str) -> Result<(), std::io::Error> {
+    let file_path = character_file(id)?;
+    let new_file_path = character_path(new_username, new_surname)?;
+    let case_only = new_file_path != file_path && new_file_path.to_lowercase() == file_path.to_lowercase();
+    if new_file_path.to_lowercase() != file_path.to_lowercase() {
+        ensure_available(&new_file_path)?;
+    }
+
+    ensure_unlocked(&file_path)?;
+    info!("Renaming Character file {} to {}", file_path, new_file_path);
+    let mut json = read_character(&file_path)?;
+    if case_only {
+        // On case-insensitive file systems both names are the same file, so writing the new one
+        // and removing the old one would delete the character. Rename the file in place instead.
+        expect_change(&file_path);
+        expect_change(&new_file_path);
+        fs::rename(&file_path, &new_file_path)?;
+    }
+    json["FirstName"] = serde_json::Value::String(new_username.into());
+    json["LastName"] = serde_json::Value::String(new_surname.into());
+    write_character(&new_file_path, &json)?;
+
+    if new_file_path != file_path && !case_only {
+        backup_character_file(&file_path, id)?;
+        expect_change(&file_path);
+        fs::remove_file(&file_path)?;
+    }
     Ok(())
 }
 
-pub async fn modify_facepaint(username: &str, surname: &str, facepaint: &str) -> Result<(), std::io::Error> {
-    info!("Setting FacePaint");
-    let file_path = format!("characters/{}{}.json", username, surname);
+/// Copies the character `id` into a new character named `new_username` `new_surname`, keeping
+/// its whole appearance.
+///
+/// # Returns
+///
+/// The id of the copy.
+pub async fn duplicate_character(id: &str, new_username: &str, new_surname: &str) -> Result<String, std::io::Error> {
+    let new_file_path = character_path(new_username, new_surname)?;
+    ensure_available(&new_file_path)?;
 
-    let mut file = File::open(&file_path)?;
-    let mut buffer: String = String::new();
-    file.read_to_string(&mut buffer)?;
-    let mut json: Value = serde_json::from_str(&buffer)?;
+    info!("Duplicating Character {} as {}{}", id, new_username, new_surname);
+    let mut json = read_character(&character_file(id)?)?;
+    let new_id = new_character_id();
+    json[ID_FIELD] = serde_json::Value::String(new_id.clone());
+    json[GUID_FIELD] = serde_json::Value::Number(new_player_guid().into());
+    json["FirstName"] = serde_json::Value::String(new_username.into());
+    json["LastName"] = serde_json::Value::String(new_surname.into());
+    write_character(&new_file_path, &json)?;
+    Ok(new_id)
+}
 
-    // Altere o valor
-    json["FacePaint"] = serde_json::Value::String(facepaint.into());
+/// Writes the character `id` to `destination`, outside the characters directory.
+///
+/// When `pretty` is set the JSON is indented to be easier to read and edit by hand.
+pub async fn export_character(id: &str, destination: &Path, pretty: bool) -> Result<(), std::io::Error> {
+    info!("Exporting Character {} to {}", id, destination.display());
+    let json = read_character(&character_file(id)?)?;
 
-    let mut file = File::create(file_path)?;
+    let mut writer = BufWriter::new(File::create(destination)?);
+    match pretty {
+        true => serde_json::to_writer_pretty(&mut writer, &json)?,
+        false => serde_json::to_writer(&mut writer, &json)?,
+    };
+    writer.flush()
+}
 
-    match serde_json::to_writer(&mut file, &json) {
-        Ok(_) => {info!("Operation finished successfully")},
-        Err(e) => {
-            eprintln!("Operation failed due to {:#?}", &e);
-            error!("Operation failed due to {:#?}", &e);
+/// Id and name of a character, e.g. the name an imported character was stored under.
+#[derive(Debug, Serialize)]
+pub struct CharacterRef {
+    pub id: String,
+    pub first_name: String,
+    pub last_name: String,
+}
+
+/// Imports the character file at `source` into the characters directory.
+///
+/// The file is validated against the character schema and the cosmetics stored in the database
+/// `db` and rejected with `std::io::ErrorKind::InvalidData` if anything is wrong.
+/// When a character with the same name already exists, a number is appended to the surname of
+/// the imported one instead of overwriting it.
+pub async fn import_character(source: &Path, db: &Database) -> Result<CharacterRef, std::io::Error> {
+    info!("Importing Character file {}", source.display());
+    let json = read_character(&source.to_string_lossy())?;
+
+    import_character_json(json, db)
+}
+
+/// Migrates and validates the character `json` before it is imported, returning its first name
+/// and surname.
+fn check_import(json: &mut Value, db: &Database) -> Result<(String, String), std::io::Error> {
+    migrate_character(json)?;
+    let issues = validate_character(json, db)?;
+    if !issues.is_empty() {
+        let issues: Vec<String> = issues.iter().map(ToString::to_string).collect();
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, issues.join("; ")));
+    }
+
+    if !Path::new(&characters_dir()).exists() {
+        fs::create_dir_all(characters_dir())?;
+    }
+    Ok((
+        json["FirstName"].as_str().unwrap_or_default().to_string(),
+        json["LastName"].as_str().unwrap_or_default().to_string(),
+    ))
+}
+
+/// Writes the checked character `json` to the characters directory as `first_name` `surname`.
+///
+/// The imported character keeps its id, unless it has none or the id is already used by another
+/// character, in which case it is given a new one.
+fn store_import(mut json: Value, first_name: String, surname: String) -> Result<CharacterRef, std::io::Error> {
+    if id_of(&json).is_empty() || character_file(id_of(&json)).is_ok() {
+        json[ID_FIELD] = serde_json::Value::String(new_character_id());
+        json[GUID_FIELD] = serde_json::Value::Number(new_player_guid().into());
+    }
+
+    write_character(&character_path(&first_name, &surname)?, &json)?;
+    Ok(CharacterRef { id: id_of(&json).to_string(), first_name, last_name: surname })
+}
+
+/// Validates the character `json` and stores it in the characters directory.
+///
+/// When a character with the same name already exists, a number is appended to the surname of
+/// the imported one. The imported character keeps its id, unless it has none or the id is
+/// already used by another character, in which case it is given a new one.
+pub fn import_character_json(mut json: Value, db: &Database) -> Result<CharacterRef, std::io::Error> {
+    let (first_name, last_name) = check_import(&mut json, db)?;
+
+    let mut surname = last_name.clone();
+    let mut suffix = 2;
+    while existing_character_file(&character_path(&first_name, &surname)?)?.is_some() {
+        surname = format!("{}{}", last_name, suffix);
+        suffix += 1;
+    }
+    if surname != last_name {
+        warn!("Character {} already exists, importing as {}", full_name(&first_name, &last_name), full_name(&first_name, &surname));
+        json["LastName"] = serde_json::Value::String(surname.clone());
+    }
+
+    store_import(json, first_name, surname)
+}
+
+/// Validates the character `json` and stores it in the characters directory, unless a
+/// character with the same name already exists, in which case it is skipped and `None` is
+/// returned.
+pub fn import_new_character_json(mut json: Value, db: &Database) -> Result<Option<CharacterRef>, std::io::Error> {
+    let (first_name, last_name) = check_import(&mut json, db)?;
+
+    if existing_character_file(&character_path(&first_name, &last_name)?)?.is_some() {
+        warn!("Character {} already exists, skipping import", full_name(&first_name, &last_name));
+        return Ok(None);
+    }
+
+    store_import(json, first_name, last_name).map(Some)
+}
+
+/// A set of appearance changes to apply to a character in one go.
+///
+/// Every field is optional, only the fields that are present are written to the character file.
+/// It covers every one of `APPEARANCE_FIELDS`, so a preset or an appearance code taken from a
+/// character reproduces it. `extras` is the beard or wings of the model, as the game reads them,
+/// while `facial_hair` and `wings` are kept whatever the model.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AppearanceDelta {
+    pub gender_race: Option<u8>,
+    pub hair_type: Option<String>,
+    pub hair_color: Option<usize>,
+    pub eye_color: Option<usize>,
+    pub skintone: Option<String>,
+    pub facepaint: Option<String>,
+    pub extras: Option<String>,
+    pub face_shape: Option<String>,
+    pub hair_highlight_color: Option<usize>,
+    pub eye_shape: Option<String>,
+    pub facepaint_color: Option<u32>,
+    pub facepaint_opacity: Option<f64>,
+    pub facial_hair: Option<String>,
+    pub wings: Option<String>,
+    pub wing_color: Option<u32>,
+    pub eyebrow_style: Option<String>,
+    pub eyebrow_color: Option<usize>,
+    pub body_type: Option<u8>,
+    pub body_scale: Option<f64>,
+    pub voice: Option<usize>,
+    pub tail: Option<String>,
+    pub ears: Option<String>,
+    pub markings: Option<Vec<usize>>,
+    pub glasses: Option<String>,
+    pub mask: Option<String>,
+    pub earrings: Option<String>,
+}
+
+impl AppearanceDelta {
+    /// Returns the character file fields written by these changes.
+    fn fields(&self) -> Vec<&'static str> {
+        let present: [(bool, &'static str); 26] = [
+            (self.gender_race.is_some(), "PlayerModel"),
+            (self.face_shape.is_some(), "PlayerHead"),
+            (self.hair_type.is_some(), "PlayerHair"),
+            (self.hair_color.is_some(), "HairColor"),
+            (self.hair_highlight_color.is_some(), "HairHighlightColor"),
+            (self.eye_color.is_some(), "EyeColor"),
+            (self.eye_shape.is_some(), "EyeShape"),
+            (self.skintone.is_some(), "Skintone"),
+            (self.facepaint.is_some(), "FacePaint"),
+            (self.facepaint_color.is_some(), "FacePaintColor"),
+            (self.facepaint_opacity.is_some(), "FacePaintOpacity"),
+            (self.extras.is_some(), EXTRAS_FIELD),
+            (self.facial_hair.is_some(), BEARD_FIELD),
+            (self.wings.is_some(), WINGS_FIELD),
+            (self.wing_color.is_some(), "WingColor"),
+            (self.eyebrow_style.is_some(), "EyebrowStyle"),
+            (self.eyebrow_color.is_some(), "EyebrowColor"),
+            (self.body_type.is_some(), "BodyType"),
+            (self.body_scale.is_some(), "BodyScale"),
+            (self.voice.is_some(), "Voice"),
+            (self.tail.is_some(), "Tail"),
+            (self.ears.is_some(), "Ears"),
+            (self.markings.is_some(), "Markings"),
+            (self.glasses.is_some(), "Glasses"),
+            (self.mask.is_some(), "Mask"),
+            (self.earrings.is_some(), "Earrings"),
+        ];
+        present.iter().filter(|(present, _)| *present).map(|(_, field)| *field).collect()
+    }
+}
+
+/// Converts `value` into a JSON number, failing with `std::io::ErrorKind::InvalidInput` if it
+/// isn't finite.
+fn float_value(field: &str, value: f64) -> Result<Value, std::io::Error> {
+    serde_json::Number::from_f64(value).map(Value::Number).ok_or_else(|| std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("{} is not a valid {}", value, field),
+    ))
+}
+
+/// Applies every change in `changes` to the character file with a single read and write.
+///
+/// This is the batched counterpart of the `modify_*` functions, meant to be used when several
+/// appearance fields are saved at once, and normalizes the values the same way: the scale and
+/// the opacity are clamped, repeated markings dropped, and cosmetics of the previous model
+/// replaced or cleared when `gender_race` changes. `facial_hair` and `wings` take precedence over
+/// `extras`. The changed fields are checked against the database `db` and nothing is written if
+/// any of them can't be rendered.
+pub async fn apply_appearance(id: &str, changes: &AppearanceDelta, db: &Database) -> Result<(), std::io::Error> {
+    info!("Applying appearance changes {:?}", changes);
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
+
+    let numbers: [(Option<u64>, &str); 8] = [
+        (changes.gender_race.map(u64::from), "PlayerModel"),
+        (changes.hair_color.map(|color| color as u64), "HairColor"),
+        (changes.hair_highlight_color.map(|color| color as u64), "HairHighlightColor"),
+        (changes.eye_color.map(|color| color as u64), "EyeColor"),
+        (changes.facepaint_color.map(u64::from), "FacePaintColor"),
+        (changes.wing_color.map(u64::from), "WingColor"),
+        (changes.eyebrow_color.map(|color| color as u64), "EyebrowColor"),
+        (changes.body_type.map(u64::from), "BodyType"),
+    ];
+    for (value, field) in numbers {
+        if let Some(value) = value {
+            json[field] = serde_json::Value::Number(value.into());
+        }
+    }
+    if let Some(voice) = changes.voice {
+        json["Voice"] = serde_json::Value::Number(voice.into());
+    }
+    if let Some(extras) = &changes.extras {
+        set_extras(&mut json, extras);
+    }
+    let strings: [(&Option<String>, &str); 13] = [
+        (&changes.face_shape, "PlayerHead"),
+        (&changes.hair_type, "PlayerHair"),
+        (&changes.eye_shape, "EyeShape"),
+        (&changes.skintone, "Skintone"),
+        (&changes.facepaint, "FacePaint"),
+        (&changes.facial_hair, BEARD_FIELD),
+        (&changes.wings, WINGS_FIELD),
+        (&changes.eyebrow_style, "EyebrowStyle"),
+        (&changes.tail, "Tail"),
+        (&changes.ears, "Ears"),
+        (&changes.glasses, "Glasses"),
+        (&changes.mask, "Mask"),
+        (&changes.earrings, "Earrings"),
+    ];
+    for (value, field) in strings {
+        if let Some(value) = value {
+            json[field] = serde_json::Value::String(value.clone());
+        }
+    }
+    if let Some(opacity) = changes.facepaint_opacity {
+        json["FacePaintOpacity"] = opacity_value(opacity)?;
+    }
+    if let Some(scale) = changes.body_scale {
+        json["BodyScale"] = body_scale_value(&json, db, scale)?;
+    }
+    if let Some(markings) = &changes.markings {
+        json["Markings"] = markings_value(markings);
+    }
+    if changes.extras.is_none() || changes.facial_hair.is_some() || changes.wings.is_some() {
+        sync_extras(&mut json);
+    }
+    let fields = changes.fields();
+    if changes.gender_race.is_some() {
+        clear_restricted_cosmetics(&mut json, db, &fields)?;
+    }
+    check_fields(&json, db, &fields)?;
+
+    write_character(&file_path, &json)
+}
+
+/// Copies `field` from `template_json` into the character `json`, removing it from the character
+/// if the template doesn't have it.
+fn restore_field(json: &mut Value, template_json: &Value, field: &str) {
+    match template_json.get(field) {
+        Some(value) if field == EXTRAS_FIELD => set_extras(json, value.as_str().unwrap_or_default()),
+        Some(value) => json[field] = value.clone(),
+        None => {
+            if let Some(character) = json.as_object_mut() {
+                character.retain(|key, _| key != field);
+            }
         },
+    }
+}
+
+/// Restores the appearance field `field` of the character `id` to its value in the template
+/// named `template`, or in the fallback template if `template` is `None`, leaving every other
+/// field untouched.
+///
+/// Fields the template doesn't have are removed from the character. Fails with
+/// `std::io::ErrorKind::InvalidInput` if `field` is not one of `APPEARANCE_FIELDS`.
+pub async fn reset_field(id: &str, field: &str, template: Option<&str>, db: &Database) -> Result<(), std::io::Error> {
+    if !APPEARANCE_FIELDS.contains(&field) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{} is not an appearance field", field),
+        ));
+    }
+    info!("Resetting {} of Character {}", field, id);
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
+    let mut template_json = load_template(template)?;
+    migrate_character(&mut template_json)?;
+
+    restore_field(&mut json, &template_json, field);
+    if field == "PlayerModel" {
+        clear_restricted_cosmetics(&mut json, db, &[])?;
+    }
+    if field != EXTRAS_FIELD {
+        sync_extras(&mut json);
+    }
+    check_fields(&json, db, &[field])?;
+
+    write_character(&file_path, &json)
+}
+
+/// Restores every appearance field of the character `id` to its value in the template named
+/// `template`, or in the fallback template if `template` is `None`.
+///
+/// Only `APPEARANCE_FIELDS` are touched, so the name, GUID, inventory and every other
+/// non-appearance data of the character are kept.
+pub async fn reset_character(id: &str, template: Option<&str>, db: &Database) -> Result<(), std::io::Error> {
+    info!("Resetting appearance of Character {}", id);
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
+    let mut template_json = load_template(template)?;
+    migrate_character(&mut template_json)?;
+
+    for field in APPEARANCE_FIELDS {
+        if field != EXTRAS_FIELD {
+            restore_field(&mut json, &template_json, field);
+        }
+    }
+    sync_extras(&mut json);
+    check_fields(&json, db, &APPEARANCE_FIELDS)?;
+
+    write_character(&file_path, &json)
+}
+
+/// Reads the current appearance of the character `id`.
+pub async fn get_appearance(id: &str) -> Result<AppearanceDelta, std::io::Error> {
+    let json = read_character(&character_file(id)?)?;
+
+    Ok(AppearanceDelta {
+        gender_race: json["PlayerModel"].as_u64().map(|model| model as u8),
+        hair_type: json["PlayerHair"].as_str().map(str::to_string),
+        hair_color: json["HairColor"].as_u64().map(|color| color as usize),
+        eye_color: json["EyeColor"].as_u64().map(|color| color as usize),
+        skintone: json["Skintone"].as_str().map(str::to_string),
+        facepaint: json["FacePaint"].as_str().map(str::to_string),
+        extras: json[EXTRAS_FIELD].as_str().map(str::to_string),
+        face_shape: json["PlayerHead"].as_str().map(str::to_string),
+        hair_highlight_color: json["HairHighlightColor"].as_u64().map(|color| color as usize),
+        eye_shape: json["EyeShape"].as_str().map(str::to_string),
+        facepaint_color: json["FacePaintColor"].as_u64().map(|color| color as u32),
+        facepaint_opacity: json["FacePaintOpacity"].as_f64(),
+        facial_hair: json[BEARD_FIELD].as_str().map(str::to_string),
+        wings: json[WINGS_FIELD].as_str().map(str::to_string),
+        wing_color: json["WingColor"].as_u64().map(|color| color as u32),
+        eyebrow_style: json["EyebrowStyle"].as_str().map(str::to_string),
+        eyebrow_color: json["EyebrowColor"].as_u64().map(|color| color as usize),
+        body_type: json["BodyType"].as_u64().map(|body_type| body_type as u8),
+        body_scale: json["BodyScale"].as_f64(),
+        voice: json["Voice"].as_u64().map(|voice| voice as usize),
+        tail: json["Tail"].as_str().map(str::to_string),
+        ears: json["Ears"].as_str().map(str::to_string),
+        markings: json["Markings"].as_array()
+            .map(|markings| markings.iter().filter_map(Value::as_u64).map(|marking| marking as usize).collect()),
+        glasses: json["Glasses"].as_str().map(str::to_string),
+        mask: json["Mask"].as_str().map(str::to_string),
+        earrings: json["Earrings"].as_str().map(str::to_string),
+    })
+}
+
+/// An appearance field whose value differs between two characters.
+#[derive(Debug, Serialize)]
+pub struct FieldDiff {
+    field: String,
+    left: Value,
+    right: Value,
+}
+
+/// Compares the appearance of the character `id` with the one of the character `other_id`.
+///
+/// # Returns
+///
+/// Every appearance field whose value differs, with the value of each character. Fields
+/// missing from a character file are reported as `null`.
+pub async fn diff_characters(id: &str, other_id: &str) -> Result<Vec<FieldDiff>, std::io::Error> {
+    let left = read_character(&character_file(id)?)?;
+    let right = read_character(&character_file(other_id)?)?;
+
+    Ok(APPEARANCE_FIELDS
+        .iter()
+        .filter(|field| left[**field] != right[**field])
+        .map(|field| FieldDiff {
+            field: field.to_string(),
+            left: left[*field].clone(),
+            right: right[*field].clone(),
+        })
+        .collect())
+}
+
+/// Gives the character `id` a random appearance for the model matching `gender`
+/// and `species`.
+///
+/// Every cosmetic is picked from the ones the database `db` offers for that model,
+/// so the result is always a valid combination.
+///
+/// # Returns
+///
+/// The appearance that was applied, so the caller can reflect it in the editor.
+pub async fn randomize_appearance(id: &str, gender: &str, species: &str, db: &Database) -> Result<AppearanceDelta, std::io::Error> {
+    info!("Randomizing appearance of {}", id);
+    let model = model_of(gender, species).ok_or_else(|| std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("There is no model for gender {} and species {}", gender, species),
+    ))?;
+
+    let hairs = get_hairs(db, gender, None)?;
+    let hair_colors = get_hair_color(db, None)?;
+    let eye_colors = get_eye_color(db, None)?;
+    let facepaints = get_facepaints(db, None)?;
+    let skintones = get_skintones(db, species, None)?;
+    let face_shapes = get_face_shapes(db, species, gender, None)?;
+    // Cosmetics of tables an older database lacks are left as they are
+    let missing_tables = missing_later_tables(db)?;
+    let has = |table: &str| !missing_tables.contains(&table);
+    let eye_shapes = if has("Eye_Shape") { get_eye_shapes(db, species, None)? } else { vec![] };
+    let eyebrows = if has("Eyebrows") { get_eyebrows(db, gender, None)? } else { vec![] };
+    let voices = if has("Voices") { get_voices(db, species, gender, None)? } else { vec![] };
+    let ears = if has("Ears") { get_ears(db, species, gender, None)? } else { vec![] };
+    let body_types = if has("Body_Type") { get_body_types(db, species, gender, None)? } else { vec![] };
+    let mut extras: Vec<String> = get_wings_by_gender_species(db, gender, species, None)?
+        .into_iter()
+        .map(|extra| extra.addr)
+        .collect();
+    if (gender, species) == ("m", "human") {
+        extras.push(String::new());
+    }
+
+    let changes = {
+        let mut rng = rand::thread_rng();
+        AppearanceDelta {
+            gender_race: Some(model),
+            hair_type: hairs.choose(&mut rng).map(|hair| hair.addr.clone()),
+            hair_color: hair_colors.choose(&mut rng).map(|color| color.color as usize),
+            eye_color: eye_colors.choose(&mut rng).map(|color| color.color as usize),
+            skintone: skintones.choose(&mut rng).map(|skintone| skintone.addr.clone()),
+            facepaint: facepaints.choose(&mut rng).map(|facepaint| facepaint.texture_alias.clone()),
+            extras: Some(extras.choose(&mut rng).cloned().unwrap_or_default()),
+            face_shape: face_shapes.choose(&mut rng).map(|face_shape| face_shape.addr.clone()),
+            eye_shape: eye_shapes.choose(&mut rng).map(|eye_shape| eye_shape.addr.clone()),
+            eyebrow_style: eyebrows.choose(&mut rng).map(|eyebrows| eyebrows.addr.clone()),
+            eyebrow_color: hair_colors.choose(&mut rng).map(|color| color.color as usize),
+            voice: voices.choose(&mut rng).map(|voice| voice.id),
+            ears: ears.choose(&mut rng).map(|ears| ears.addr.clone()),
+            body_type: body_types.choose(&mut rng).map(|body_type| body_type.id),
+            ..AppearanceDelta::default()
+        }
     };
-    Ok(())
-}
\ No newline at end of file
+
+    apply_appearance(id, &changes, db).await?;
+    Ok(changes)
+}
+
+/// Restores the character file of the character `id` from the backup named `backup`.
+///
+/// The current character file is itself backed up before being replaced, so restoring can be
+/// undone by restoring that newer backup.
+pub async fn restore_backup(id: &str, backup: &str) -> Result<(), std::io::Error> {
+    info!("Restoring backup {}", backup);
+    let mut json = read_character(&backup_path(id, backup)?)?;
+    json[ID_FIELD] = serde_json::Value::String(id.into());
+
+    write_character(&character_file(id)?, &json)
+}
+
+/// Restores the character file of the character `id` to its saved version `version_id`.
+pub async fn restore_version(id: &str, version_id: &str) -> Result<(), std::io::Error> {
+    info!("Restoring version {}", version_id);
+    let mut json = load_version(id, version_id)?;
+    json[ID_FIELD] = serde_json::Value::String(id.into());
+
+    write_character(&character_file(id)?, &json)
+}
+
+/// Returns an object holding only the appearance fields of the character `json`.
+fn appearance_of(json: &Value) -> Value {
+    let mut appearance = serde_json::Map::new();
+    for field in APPEARANCE_FIELDS {
+        if let Some(value) = json.get(field) {
+            appearance.insert(field.to_string(), value.clone());
+        }
+    }
+    Value::Object(appearance)
+}
+
+/// Copies every appearance field of `appearance` into the character `json`.
+fn apply_appearance_fields(json: &mut Value, appearance: &Value) {
+    for field in APPEARANCE_FIELDS {
+        if let Some(value) = appearance.get(field) {
+            json[field] = value.clone();
+        }
+    }
+}
+
+#[derive(Default)]
+struct History {
+    undo: Vec<Value>,
+    redo: Vec<Value>,
+}
+
+/// In-memory undo/redo history of appearance edits, kept per character for the current session.
+///
+/// Every entry is a snapshot of the appearance fields taken right before a change was applied,
+/// so stepping back and forth only touches appearance and leaves the rest of the file alone.
+#[derive(Default)]
+pub struct EditHistory(Mutex<HashMap<String, History>>);
+
+impl EditHistory {
+    /// Runs `change` on the character `id` and records it in the history.
+    ///
+    /// Nothing is recorded if `change` fails. A successful change clears the redo stack.
+    pub async fn track<F, T>(&self, id: &str, change: F) -> Result<T, std::io::Error>
+    where
+        F: Future<Output = Result<T, std::io::Error>>,
+    {
+        let before = appearance_of(&read_character(&character_file(id)?)?);
+        let result = change.await?;
+
+        let mut histories = self.0.lock().unwrap();
+        let history = histories.entry(id.to_string()).or_default();
+        history.undo.push(before);
+        history.redo.clear();
+        Ok(result)
+    }
+
+    /// Reverts the last recorded change of the character `id`.
+    ///
+    /// Returns `false` when there is nothing left to undo.
+    pub fn undo(&self, id: &str) -> Result<bool, std::io::Error> {
+        info!("Undoing last change");
+        let mut histories = self.0.lock().unwrap();
+        let history = histories.entry(id.to_string()).or_default();
+        step(id, &mut history.undo, &mut history.redo)
+    }
+
+    /// Reapplies the last undone change of the character `id`.
+    ///
+    /// Returns `false` when there is nothing left to redo.
+    pub fn redo(&self, id: &str) -> Result<bool, std::io::Error> {
+        info!("Redoing last undone change");
+        let mut histories = self.0.lock().unwrap();
+        let history = histories.entry(id.to_string()).or_default();
+        step(id, &mut history.redo, &mut history.undo)
+    }
+}
+
+/// Per-character locks, so the commands changing one character run strictly one after another
+/// instead of interleaving their reads and writes and dropping each other's changes.
+#[derive(Default)]
+pub struct CharacterLocks(Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>);
+
+impl CharacterLocks {
+    /// Waits until no other command is changing the character `id`. The character stays locked
+    /// until the returned guard is dropped.
+    pub async fn lock(&self, id: &str) -> tokio::sync::OwnedMutexGuard<()> {
+        let lock = self.0.lock().unwrap().entry(id.to_string()).or_default().clone();
+        lock.lock_owned().await
+    }
+}
+
+/// Characters opened for editing in memory, keyed by character id.
+///
+/// While a session is open, `set_field` only changes the copy held here, and the character file
+/// is written once on `save`. Changes written to the file by other commands in the meantime are
+/// overwritten by the save.
+#[derive(Default)]
+pub struct EditSessions(Mutex<HashMap<String, Value>>);
+
+impl EditSessions {
+    /// Opens a session for the character `id`, or reopens it from the file if one was already
+    /// open, and returns the character content.
+    pub fn open(&self, id: &str) -> Result<Value, std::io::Error> {
+        info!("Opening editing session for Character {}", id);
+        let json = read_character(&character_file(id)?)?;
+        self.0.lock().unwrap().insert(id.to_string(), json.clone());
+        Ok(json)
+    }
+
+    /// Sets the appearance field `field` of the character `id` to `value`, in memory only.
+    ///
+    /// The field is checked against the database `db` and left unchanged if it
+    /// can't be rendered. Fails with `std::io::ErrorKind::NotFound` if no session is open for
+    /// the character.
+    pub fn set_field(&self, id: &str, field: &str, value: Value, db: &Database) -> Result<(), std::io::Error> {
+        if !APPEARANCE_FIELDS.contains(&field) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{} is not an appearance field", field),
+            ));
+        }
+        let mut sessions = self.0.lock().unwrap();
+        let session = sessions.get_mut(id).ok_or_else(|| std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("There is no editing session open for Character {}", id),
+        ))?;
+
+        let mut json = session.clone();
+        if field == EXTRAS_FIELD {
+            set_extras(&mut json, value.as_str().unwrap_or_default());
+        } else {
+            json[field] = value;
+        }
+        if field == "PlayerModel" {
+            clear_restricted_cosmetics(&mut json, db, &[])?;
+        }
+        if field != EXTRAS_FIELD {
+            sync_extras(&mut json);
+        }
+        check_fields(&json, db, &[field])?;
+
+        *session = json;
+        Ok(())
+    }
+
+    /// Writes the session of the character `id` to its character file. The session stays open.
+    pub fn save(&self, id: &str) -> Result<(), std::io::Error> {
+        info!("Saving editing session of Character {}", id);
+        let sessions = self.0.lock().unwrap();
+        let json = sessions.get(id).ok_or_else(|| std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("There is no editing session open for Character {}", id),
+        ))?;
+        write_character(&character_file(id)?, json)
+    }
+
+    /// Returns `true` if a session is open for the character `id`.
+    pub fn is_open(&self, id: &str) -> bool {
+        self.0.lock().unwrap().contains_key(id)
+    }
+
+    /// Closes the session of the character `id` without writing it.
+    ///
+    /// Returns `false` if no session was open.
+    pub fn discard(&self, id: &str) -> bool {
+        info!("Discarding editing session of Character {}", id);
+        self.0.lock().unwrap().remove(id).is_some()
+    }
+}
+
+/// Applies the newest snapshot of `from` to the character file and pushes the appearance it
+/// replaced onto `to`.
+fn step(id: &str, from: &mut Vec<Value>, to: &mut Vec<Value>) -> Result<bool, std::io::Error> {
+    let snapshot = match from.last() {
+        Some(snapshot) => snapshot,
+        None => return Ok(false),
+    };
+
+    let file_path = character_file(id)?;
+    let mut json = read_character(&file_path)?;
+    let current = appearance_of(&json);
+    apply_appearance_fields(&mut json, snapshot);
+    write_character(&file_path, &json)?;
+
+    from.pop();
+    to.push(current);
+    Ok(true)
+}
+
+/// Maps the `PlayerModel` of a character to its gender and species, as used by the database.
+pub fn gender_species_of(model: u64) -> (&'static str, &'static str) {
+    match model_by_id(model) {
+        Some(model) => (model.gender.as_str(), model.species.as_str()),
+        None => ("", ""),
+    }
+}
+
+/// Maps a gender and species, as used by the database, to the matching `PlayerModel`.
+pub fn model_of(gender: &str, species: &str) -> Option<u8> {
+    match (Gender::parse(gender), Species::parse(species)) {
+        (Some(gender), Some(species)) => Some(model_for(gender, species).id),
+        _ => None,
+    }
+}
+
+/// Returns a summary of every character file, used to let the user pick an existing character.
+///
+/// The characters are listed in `order`, or by file name if it is `None`. Only favorites are
+/// listed if `favorites_only` is set.
+pub async fn list_characters(order: Option<CharacterOrder>, favorites_only: bool) -> Result<Vec<CharacterSummary>, std::io::Error> {
+    let mut characters = crate::index::list_characters()?;
+    if favorites_only {
+        characters.retain(|character| character.favorite);
+    }
+    match order.unwrap_or_default() {
+        CharacterOrder::FileName => {},
+        CharacterOrder::FavoritesFirst => characters.sort_by_key(|character| !character.favorite),
+        CharacterOrder::RecentlyUpdated => characters.sort_by(|a, b| b.updated_at().cmp(&a.updated_at())),
+    }
+    Ok(characters)
+}
@@ -1,54 +1,732 @@
-use log::error;
-use rusqlite::{Connection, Result};
-use serde::Serialize;
+use std::{
+    any::Any,
+    collections::{BTreeMap, HashMap},
+    ops::Deref,
+    path::Path,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use log::{debug, error, info, warn};
+use rusqlite::{types::Value, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize)]
+use crate::error::DatabaseError;
+use crate::model::MODELS;
+use crate::schema::{create_missing_tables, migrate};
+use crate::settings::database_path;
+
+#[derive(Debug, Clone, Serialize)]
 pub struct FacePaint {
-    id: usize,
-    texture_alias: String
+    pub id: usize,
+    pub texture_alias: String
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Hair {
-    id: usize,
-    addr: String,
-    name: String
+    pub id: usize,
+    pub addr: String,
+    pub name: String,
+    /// Rarity of the hair, e.g. `"rare"`, from the optional `rarity` column.
+    pub rarity: Option<String>,
+    /// What unlocks the hair on servers gating it, e.g. `"membership"`, from the optional
+    /// `unlock_flag` column. `None` for hairs available to everyone.
+    pub unlock_flag: Option<String>,
 }
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct PixieWings {
-    id: usize,
-    addr: String,
+    pub id: usize,
+    pub addr: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct EyeColor {
-    name: String,
-    color: u8
+    pub name: String,
+    pub color: u8
 }
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct HairColor {
-    name: String,
-    color: u8
+    pub name: String,
+    pub color: u8
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FacePaintColor {
+    pub name: String,
+    pub color: u32
+}
+
+/// A skintone a species can have, as written to `Skintone`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Skintone {
+    pub id: usize,
+    pub name: String,
+    pub species: String,
+    pub addr: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WingColor {
+    pub name: String,
+    pub color: u32
+}
+
+/// Palette a color id belongs to, serialized in snake case, e.g. `"hair_highlight"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaletteKind {
+    Eye,
+    Hair,
+    HairHighlight,
+    Wing,
+    FacePaint,
+}
+
+impl PaletteKind {
+    /// Returns the name of the palette, as serialized and stored in the `kind` column.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PaletteKind::Eye => "eye",
+            PaletteKind::Hair => "hair",
+            PaletteKind::HairHighlight => "hair_highlight",
+            PaletteKind::Wing => "wing",
+            PaletteKind::FacePaint => "face_paint",
+        }
+    }
+}
+
+/// The actual color a color id stands for, so it can be shown as a swatch.
+#[derive(Debug, Clone, Serialize)]
+pub struct PaletteColor {
+    pub color: u32,
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+    /// The color as a CSS hex color, e.g. `#a0522d`.
+    pub hex: String,
 }
 
-#[derive(Debug, Serialize)]
+impl PaletteColor {
+    /// Returns the color `color` stands for from its `hex` notation, `#rrggbb` or `rrggbb`,
+    /// `None` if `hex` isn't one.
+    fn parse(color: u32, hex: &str) -> Option<PaletteColor> {
+        let digits = hex.trim().trim_start_matches('#');
+        if digits.len() != 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let channel = |start: usize| u8::from_str_radix(&digits[start..start + 2], 16).ok();
+        let (red, green, blue) = (channel(0)?, channel(2)?, channel(4)?);
+        Some(PaletteColor { color, red, green, blue, hex: format!("#{:02x}{:02x}{:02x}", red, green, blue) })
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct Extras {
-    id: usize,
-    name: String,
-    species: String,
-    gender: String,
-    addr: String
+    pub id: usize,
+    pub name: String,
+    pub species: String,
+    pub gender: String,
+    pub addr: String,
+    /// Rarity of the extra, from the optional `rarity` column.
+    pub rarity: Option<String>,
+    /// What unlocks the extra on servers gating it, from the optional `unlock_flag` column.
+    pub unlock_flag: Option<String>,
+}
+
+/// Kind of an extra, taken from the part of its address naming it, e.g. `facialhair` in
+/// `human_m_facialhair_beard.adr`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExtraCategory {
+    Wings,
+    Beards,
+    Horns,
+    Auras,
+    Other,
+}
+
+impl ExtraCategory {
+    /// Returns the category of the extra at `addr`, `Other` for addresses of an unknown kind.
+    pub fn of(addr: &str) -> ExtraCategory {
+        match addr.split('_').nth(2) {
+            Some("wings") => ExtraCategory::Wings,
+            Some("facialhair") => ExtraCategory::Beards,
+            Some("horns") => ExtraCategory::Horns,
+            Some("aura") => ExtraCategory::Auras,
+            _ => ExtraCategory::Other,
+        }
+    }
+}
+
+/// Table of cosmetics the creator offers, serialized in snake case, e.g. `"face_shape"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CosmeticCategory {
+    Hair,
+    Extras,
+    FacePaint,
+    Eyebrows,
+    FaceShape,
+    Tail,
+    Ears,
+    Marking,
+    Skintone,
+    EyeShape,
+    Accessory,
+}
+
+impl CosmeticCategory {
+    pub const ALL: [CosmeticCategory; 11] = [
+        CosmeticCategory::Hair,
+        CosmeticCategory::Extras,
+        CosmeticCategory::FacePaint,
+        CosmeticCategory::Eyebrows,
+        CosmeticCategory::FaceShape,
+        CosmeticCategory::Tail,
+        CosmeticCategory::Ears,
+        CosmeticCategory::Marking,
+        CosmeticCategory::Skintone,
+        CosmeticCategory::EyeShape,
+        CosmeticCategory::Accessory,
+    ];
+
+    /// Returns the name of the category, as serialized.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CosmeticCategory::Hair => "hair",
+            CosmeticCategory::Extras => "extras",
+            CosmeticCategory::FacePaint => "face_paint",
+            CosmeticCategory::Eyebrows => "eyebrows",
+            CosmeticCategory::FaceShape => "face_shape",
+            CosmeticCategory::Tail => "tail",
+            CosmeticCategory::Ears => "ears",
+            CosmeticCategory::Marking => "marking",
+            CosmeticCategory::Skintone => "skintone",
+            CosmeticCategory::EyeShape => "eye_shape",
+            CosmeticCategory::Accessory => "accessory",
+        }
+    }
+
+    /// Returns the table holding the category and the columns holding the name, the value
+    /// written to the character, the species and the gender of its rows, `NULL` for the columns
+    /// the table doesn't have.
+    fn columns(self) -> (&'static str, &'static str, &'static str, &'static str, &'static str) {
+        match self {
+            CosmeticCategory::Hair => ("Hair", "name", "addr", "NULL", "gender"),
+            CosmeticCategory::Extras => ("extras", "name", "addr", "species", "gender"),
+            CosmeticCategory::FacePaint => ("FacePaint", "texture_alias", "texture_alias", "NULL", "NULL"),
+            CosmeticCategory::Eyebrows => ("Eyebrows", "name", "addr", "NULL", "gender"),
+            CosmeticCategory::FaceShape => ("FaceShapes", "name", "addr", "species", "gender"),
+            CosmeticCategory::Tail => ("Tails", "name", "addr", "species", "NULL"),
+            CosmeticCategory::Ears => ("Ears", "name", "addr", "species", "gender"),
+            CosmeticCategory::Marking => ("Markings", "name", "id", "species", "gender"),
+            CosmeticCategory::Skintone => ("Skintone", "name", "addr", "species", "NULL"),
+            CosmeticCategory::EyeShape => ("Eye_Shape", "name", "addr", "species", "NULL"),
+            CosmeticCategory::Accessory => ("Accessories", "name", "addr", "species", "gender"),
+        }
+    }
+}
+
+/// A cosmetic of any category, with the value to write to the character, e.g. the address of a
+/// hair or the id of a marking.
+#[derive(Debug, Clone, Serialize)]
+pub struct Cosmetic {
+    pub category: CosmeticCategory,
+    pub id: usize,
+    pub name: String,
+    pub value: String,
+    /// Species the cosmetic is available to, `None` if it is available to every species.
+    pub species: Option<String>,
+    /// Gender the cosmetic is available to, `None` if it is available to every gender.
+    pub gender: Option<String>,
+    /// Rarity of the cosmetic, e.g. `"rare"`, from the optional `rarity` column.
+    pub rarity: Option<String>,
+    /// What unlocks the cosmetic on servers gating it, e.g. `"membership"`, from the optional
+    /// `unlock_flag` column. `None` for cosmetics available to everyone.
+    pub unlock_flag: Option<String>,
+}
+
+/// Criteria a cosmetic must all meet to be listed, criteria left out match every cosmetic.
+///
+/// Cosmetics of a category without species or gender, e.g. face paints, match every species and
+/// gender. Tags are read from the `Cosmetic_Tag` table, whose rows give a `tag` to the cosmetic
+/// of `category` with the `value`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct CosmeticFilter {
+    pub gender: Option<String>,
+    pub species: Option<String>,
+    pub category: Option<CosmeticCategory>,
+    /// Tags the cosmetic must all have.
+    pub tags: Vec<String>,
+}
+
+impl CosmeticFilter {
+    /// Returns the `WHERE` clause selecting the cosmetics of `category` meeting the filter, and
+    /// those whose name is like `name_pattern` if there is one, with its parameters.
+    fn conditions(&self, db: &Database, conn: &Connection, category: CosmeticCategory, name_pattern: Option<&str>) -> Result<(String, Vec<Value>), DatabaseError> {
+        let (table, name, value, species, gender) = category.columns();
+        let mut conditions: Vec<String> = vec![];
+        let mut params: Vec<Value> = vec![];
+
+        if let (Some(filter), true) = (&self.gender, gender != "NULL") {
+            conditions.push(format!("{} = ?", gender));
+            params.push(Value::Text(filter.clone()));
+        }
+        if let (Some(filter), true) = (&self.species, species != "NULL") {
+            conditions.push(format!("{} = ?", species));
+            params.push(Value::Text(filter.clone()));
+        }
+        for tag in &self.tags {
+            conditions.push(format!(
+                "EXISTS (SELECT 1 FROM Cosmetic_Tag WHERE category = ? AND value = CAST({} AS TEXT) AND tag = ?)",
+                value
+            ));
+            params.push(Value::Text(category.as_str().to_string()));
+            params.push(Value::Text(tag.clone()));
+        }
+        if let Some(pattern) = name_pattern {
+            conditions.push(format!("{} LIKE ? ESCAPE '\\'", name));
+            params.push(Value::Text(pattern.to_string()));
+        }
+
+        let conditions: Vec<&str> = conditions.iter().map(String::as_str).collect();
+        Ok((db.filter_rows(conn, table, &conditions)?, params))
+    }
+
+    /// Returns the query selecting the cosmetics of `category` meeting the filter, and those whose
+    /// name is like `name_pattern` if there is one, in the order `sort` and only the rows on
+    /// `page` if there is one, with its parameters. Paged queries end their order with `rowid`,
+    /// so rows sorting alike can't move between pages.
+    fn query(&self, db: &Database, conn: &Connection, category: CosmeticCategory, name_pattern: Option<&str>, sort: Option<CosmeticSort>, page: Option<Page>) -> Result<(String, Vec<Value>), DatabaseError> {
+        let (table, name, value, species, gender) = category.columns();
+        let (conditions, mut params) = self.conditions(db, conn, category, name_pattern)?;
+        let mut order = order_by(conn, table, sort, name)?;
+        if page.is_some() && !order.ends_with("rowid") {
+            order.push_str(", rowid");
+        }
+        let mut sql = format!(
+            "SELECT id, {}, CAST({} AS TEXT), {}, {}, {}, {} FROM {}{}{}",
+            name, value, species, gender,
+            optional_column(conn, table, "rarity")?,
+            optional_column(conn, table, "unlock_flag")?,
+            table,
+            conditions,
+            order,
+        );
+        if let Some(page) = page {
+            sql.push_str(" LIMIT ? OFFSET ?");
+            params.push(Value::Integer(page.limit as i64));
+            params.push(Value::Integer(page.offset as i64));
+        }
+        Ok((sql, params))
+    }
+
+    /// Returns the query counting the cosmetics of `category` meeting the filter, with its
+    /// parameters.
+    fn count_query(&self, db: &Database, conn: &Connection, category: CosmeticCategory) -> Result<(String, Vec<Value>), DatabaseError> {
+        let (table, ..) = category.columns();
+        let (conditions, params) = self.conditions(db, conn, category, None)?;
+        Ok((format!("SELECT COUNT(*) FROM {}{}", table, conditions), params))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Eyebrows {
+    pub id: usize,
+    pub name: String,
+    pub gender: String,
+    pub addr: String,
+}
+
+/// A head the game can put on a character, as written to `PlayerHead`.
+#[derive(Debug, Clone, Serialize)]
+pub struct FaceShape {
+    pub id: usize,
+    pub name: String,
+    pub species: String,
+    pub gender: String,
+    pub addr: String,
+}
+
+/// The body types and scales a species can use, both bounds included.
+#[derive(Debug, Clone, Serialize)]
+pub struct BodyRange {
+    pub species: String,
+    pub min_body_type: u8,
+    pub max_body_type: u8,
+    pub min_scale: f64,
+    pub max_scale: f64,
+}
+
+/// A named body type a model can use, as written to `BodyType`.
+#[derive(Debug, Clone, Serialize)]
+pub struct BodyType {
+    pub id: u8,
+    pub name: String,
+    pub species: String,
+    pub gender: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Voice {
+    pub id: usize,
+    pub name: String,
+    pub species: String,
+    pub gender: String,
+}
+
+/// A title a character can display next to its name.
+#[derive(Debug, Clone, Serialize)]
+pub struct Title {
+    pub id: u64,
+    pub place: u64,
+    /// Id of the localized string naming the title in the game client.
+    pub title_name: u64,
+}
+
+/// A class, or job, a character can play as, identified by its `JobGUID`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Class {
+    pub id: u64,
+    pub name: String,
+    /// Id of the localized string naming the job in the game client.
+    pub job_name: u64,
+}
+
+/// An item of the starting inventory that can be equipped, identified by its `Guid` in
+/// `ClientItems`.
+#[derive(Debug, Clone, Serialize)]
+pub struct EquipmentItem {
+    pub guid: u64,
+    pub name: String,
+    pub slot: u8,
+}
+
+/// An eye geometry a species can use, as written to `EyeShape`.
+#[derive(Debug, Clone, Serialize)]
+pub struct EyeShape {
+    pub id: usize,
+    pub name: String,
+    pub species: String,
+    pub addr: String,
+}
+
+/// An accessory a model can wear in one of the accessory slots, e.g. glasses.
+#[derive(Debug, Clone, Serialize)]
+pub struct Accessory {
+    pub id: usize,
+    pub name: String,
+    pub slot: String,
+    pub species: String,
+    pub gender: String,
+    pub addr: String,
+}
+
+/// A combination of values of two character fields the game can't render, e.g. a hat clipping
+/// through a hair, or wings on a human.
+///
+/// Values are compared to the text of the fields, numbers included, and `*` in a value matches
+/// any text, e.g. `*_hair_mohawk_*`. Empty fields never match, as they hold no cosmetic.
+#[derive(Debug, Clone, Serialize)]
+pub struct Constraint {
+    pub id: usize,
+    pub field: String,
+    pub value: String,
+    pub other_field: String,
+    pub other_value: String,
+    /// Why the combination is refused, shown to the user.
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Tail {
+    pub id: usize,
+    pub name: String,
+    pub species: String,
+    pub addr: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Ears {
+    pub id: usize,
+    pub name: String,
+    pub species: String,
+    pub gender: String,
+    pub addr: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Marking {
+    pub id: usize,
+    pub name: String,
+    pub species: String,
+    pub gender: String,
+    pub addr: String,
+}
+
+/// The model the game uses for one gender of a species, as written to `PlayerModel`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SpeciesModel {
+    pub model: u8,
+    pub species: String,
+    pub gender: String,
+    pub name: String,
+}
+
+/// Opens the SQLite database at `path`, upgrading its schema if it is older than this build and
+/// creating the tables it lacks that have default rows.
+///
+/// Unlike `Connection::open`, a missing file is reported instead of silently created empty, so
+/// a wrong database path in the settings doesn't show up as missing tables. A database that
+/// can't be upgraded, e.g. a read-only file, is still opened, as it may lack only tables or
+/// columns that are optional.
+fn open(path: &str) -> Result<Connection, DatabaseError> {
+    if !Path::new(path).is_file() {
+        return Err(DatabaseError::Missing { path: path.to_string() });
+    }
+    info!("Opening Database file {}", path);
+    let mut conn = Connection::open(path)?;
+    if let Err(e) = migrate(&mut conn) {
+        error!("Error ocurred while migrating the Database file {}, due to {:#?}", path, &e);
+    }
+    if let Err(e) = create_missing_tables(&mut conn) {
+        error!("Error ocurred while creating the missing tables of the Database file {}, due to {:#?}", path, &e);
+    }
+    Ok(conn)
+}
+
+/// Every cosmetic the creation form offers for one model, so switching species or gender takes a
+/// single call.
+#[derive(Debug, Clone, Serialize)]
+pub struct CustomizationOptions {
+    pub hairs: Vec<Hair>,
+    pub eye_colors: Vec<EyeColor>,
+    pub hair_colors: Vec<HairColor>,
+    pub facepaints: Vec<FacePaint>,
+    pub skintones: Vec<Skintone>,
+    pub extras: Vec<Extras>,
+}
+
+/// Connections kept open once their query is done, more are opened while many commands query
+/// the database at once.
+const MAX_IDLE_CONNECTIONS: usize = 4;
+
+/// The cosmetics database, managed by Tauri so every command shares a pool of connections
+/// instead of opening the file for each query.
+///
+/// Connections are opened on demand, so commands loading several lists at once query in
+/// parallel, and the idle ones are kept for later queries. Idle connections to another file are
+/// closed whenever the database path of the settings changes, e.g. after switching profiles.
+///
+/// The cosmetics never change while the app runs, so every list is cached once read, see
+/// `cached`. Clones share the same pool and cache, so a query can be moved to a blocking thread
+/// with `run`.
+///
+/// Tables may have an `enabled` column, so unreleased or broken cosmetics can be hidden without
+/// deleting them. Rows where it is `0` are left out of every list, but for the view returned by
+/// `including_disabled`.
+#[derive(Clone, Default)]
+pub struct Database {
+    idle: Arc<Mutex<Vec<(String, Connection)>>>,
+    cache: Arc<Mutex<HashMap<CacheKey, Arc<dyn Any + Send + Sync>>>>,
+    include_disabled: bool,
+}
+
+/// Key of a cached list: the database path, the query, the filters and the order it was run
+/// with, and whether it includes disabled rows.
+type CacheKey = (String, &'static str, Vec<String>, Option<CosmeticSort>, bool);
+
+/// Order of a cosmetic list, serialized in snake case, e.g. `"display_order"`. Without one, lists
+/// follow the `display_order` column of tables having one, see `order_by`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CosmeticSort {
+    /// By the name shown to the user.
+    Name,
+    /// By id, or by the order the rows were inserted in for tables without an id.
+    Id,
+    /// By the `display_order` column curators can add to a table, by name for tables without one.
+    DisplayOrder,
+}
+
+/// Returns `true` if the database has a table named `table`.
+pub fn has_table(conn: &Connection, table: &str) -> Result<bool, DatabaseError> {
+    let mut stmt = conn.prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?")?;
+    Ok(stmt.exists([table])?)
+}
+
+/// Returns `true` if `table` has a column named `column`.
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool, DatabaseError> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let mut columns = stmt.query_map([], |row| row.get::<_, String>(1))?;
+    Ok(columns.any(|name| name.map_or(false, |name| name == column)))
+}
+
+/// Returns `column` if `table` has it, or `NULL` to select in its place otherwise, for the
+/// optional columns of the cosmetics tables.
+fn optional_column(conn: &Connection, table: &str, column: &'static str) -> Result<&'static str, DatabaseError> {
+    Ok(match has_column(conn, table, column)? {
+        true => column,
+        false => "NULL",
+    })
+}
+
+/// Returns the `ORDER BY` clause sorting a query of `table` by `sort`, `name_column` being the
+/// column holding the name of the table's rows.
+///
+/// Tables may have a `display_order` column, so curators can order a list without renaming its
+/// rows. Lists without a `sort` follow it if the table has one, rows without an order coming
+/// last by name, and are sorted by name otherwise, ties keeping the order of the database.
+/// Sorting by display order a table without the column sorts it by name.
+fn order_by(conn: &Connection, table: &str, sort: Option<CosmeticSort>, name_column: &str) -> Result<String, DatabaseError> {
+    let by_display_order = match sort {
+        None | Some(CosmeticSort::DisplayOrder) => has_column(conn, table, "display_order")?,
+        _ => false,
+    };
+    Ok(match sort {
+        _ if by_display_order => format!(" ORDER BY display_order IS NULL, display_order, {} COLLATE NOCASE", name_column),
+        None => format!(" ORDER BY {} COLLATE NOCASE, rowid", name_column),
+        Some(CosmeticSort::Name) | Some(CosmeticSort::DisplayOrder) => format!(" ORDER BY {} COLLATE NOCASE", name_column),
+        Some(CosmeticSort::Id) => " ORDER BY rowid".to_string(),
+    })
+}
+
+/// A page of a cosmetic list, the `limit` rows following the first `offset` ones, so a modded
+/// database with thousands of rows isn't read nor sent to the frontend at once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct Page {
+    pub limit: usize,
+    #[serde(default)]
+    pub offset: usize,
+}
+
+/// A connection to the cosmetics database, handed back to the pool once dropped.
+pub struct DatabaseConnection<'a> {
+    db: &'a Database,
+    path: String,
+    conn: Option<Connection>,
+}
+
+impl Deref for DatabaseConnection<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().expect("the connection is only taken when dropped")
+    }
+}
+
+impl Drop for DatabaseConnection<'_> {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            let mut idle = self.db.idle_connections();
+            if idle.len() < MAX_IDLE_CONNECTIONS {
+                idle.push((std::mem::take(&mut self.path), conn));
+            }
+        }
+    }
+}
+
+impl Database {
+    fn idle_connections(&self) -> MutexGuard<'_, Vec<(String, Connection)>> {
+        self.idle.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn cache(&self) -> MutexGuard<'_, HashMap<CacheKey, Arc<dyn Any + Send + Sync>>> {
+        self.cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Returns a connection to the database of the settings, reusing an idle one if possible.
+    pub fn connection(&self) -> Result<DatabaseConnection<'_>, DatabaseError> {
+        let path = database_path();
+        let idle = {
+            let mut idle = self.idle_connections();
+            idle.retain(|(opened, _)| *opened == path);
+            idle.pop()
+        };
+        let conn = match idle {
+            Some((_, conn)) => conn,
+            None => open(&path)?,
+        };
+        Ok(DatabaseConnection { db: self, path, conn: Some(conn) })
+    }
+
+    /// Runs `query` on a blocking thread, so a slow query holds up neither the async runtime nor
+    /// the UI.
+    pub async fn run<T, F>(&self, query: F) -> Result<T, DatabaseError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Database) -> Result<T, DatabaseError> + Send + 'static,
+    {
+        let db = self.clone();
+        tauri::async_runtime::spawn_blocking(move || query(&db))
+            .await
+            .map_err(|e| DatabaseError::Interrupted { message: e.to_string() })?
+    }
+
+    /// Returns the list `query` read with `filters` in the order `sort` from the database of the
+    /// settings, running `read` only if it isn't cached yet. Errors are not cached, so a missing
+    /// database is read again on the next call.
+    fn cached<T, F>(&self, query: &'static str, filters: &[&str], sort: Option<CosmeticSort>, read: F) -> Result<Vec<T>, DatabaseError>
+    where
+        T: Clone + Send + Sync + 'static,
+        F: FnOnce() -> Result<Vec<T>, DatabaseError>,
+    {
+        let key: CacheKey = (
+            database_path(),
+            query,
+            filters.iter().map(|filter| filter.to_string()).collect(),
+            sort,
+            self.include_disabled,
+        );
+        if let Some(list) = self.cache().get(&key).and_then(|list| list.downcast_ref::<Vec<T>>()) {
+            return Ok(list.clone());
+        }
+
+        let list = read()?;
+        debug!("Caching {} {:?} of {}", query, key.2, key.0);
+        self.cache().insert(key, Arc::new(list.clone()));
+        Ok(list)
+    }
+
+    /// Returns a view of the database whose lists include disabled rows, sharing the pool and
+    /// cache of this one, e.g. so characters already wearing a disabled cosmetic stay valid.
+    pub fn including_disabled(&self) -> Database {
+        Database { include_disabled: true, ..self.clone() }
+    }
+
+    /// Returns the `WHERE` clause selecting the rows of `table` meeting every one of
+    /// `conditions`, and leaving out disabled rows unless this view includes them.
+    fn filter_rows(&self, conn: &Connection, table: &str, conditions: &[&str]) -> Result<String, DatabaseError> {
+        let mut conditions = conditions.to_vec();
+        if !self.include_disabled && has_column(conn, table, "enabled")? {
+            conditions.push("enabled IS NOT 0");
+        }
+        Ok(match conditions.is_empty() {
+            true => String::new(),
+            false => format!(" WHERE {}", conditions.join(" AND ")),
+        })
+    }
+
+    /// Forgets every cached list, so the next queries read the database again, e.g. after the
+    /// database file was replaced or edited by hand.
+    pub fn invalidate(&self) {
+        info!("Clearing the cosmetics cache");
+        self.cache().clear();
+        self.idle_connections().clear();
+    }
 }
 
 /// Retrieves eye color data from a SQLite database.
 ///
 /// # Arguments
 ///
-/// * `path` - A string representing the path to the SQLite database file.
+/// * `db` - The cosmetics database.
+/// * `sort` - The order of the list, `None` for the display order, or by name.
 ///
 /// # Returns
 ///
-/// Returns a `Result` containing a vector of `EyeColor` structs or a `rusqlite::Error` if an
+/// Returns a `Result` containing a vector of `EyeColor` structs or a `DatabaseError` if an
 /// error occurs during the database operation.
 ///
 /// # Example
@@ -56,10 +734,10 @@ pub struct Extras {
 /// ```rust
 /// use your_module::get_eye_color;
 ///
-/// // Provide the path to your SQLite database file
-/// let path = "path/to/your/database.db";
+/// // The database is usually the one managed by Tauri
+/// let db = Database::default();
 ///
-/// match get_eye_color(path) {
+/// match get_eye_color(&db, None) {
 ///     Ok(eye_colors) => {
 ///         // Successfully retrieved eye colors
 ///         for color in eye_colors {
@@ -72,40 +750,47 @@ pub struct Extras {
 ///     }
 /// }
 /// ```
-pub fn get_eye_color(path: &str) -> Result<Vec<EyeColor>, rusqlite::Error> {
-    let conn = Connection::open(path).unwrap();
-    let mut eye_colors: Vec<EyeColor> = vec![];
+pub fn get_eye_color(db: &Database, sort: Option<CosmeticSort>) -> Result<Vec<EyeColor>, DatabaseError> {
+    db.cached("eye_color", &[], sort, || {
+        let conn = db.connection()?;
+        let mut eye_colors: Vec<EyeColor> = vec![];
 
-    let mut stmt = conn.prepare("SELECT name, color FROM Eye_Color")?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT name, color FROM Eye_Color{}{}",
+            db.filter_rows(&conn, "Eye_Color", &[])?,
+            order_by(&conn, "Eye_Color", sort, "name")?,
+        ))?;
 
-    let eye_iter = stmt.query_map([], |row| {
-        Ok(
-            EyeColor {
-                name: row.get(0)?,
-                color: row.get(1)?
-            }
-        )
-    })?;
+        let eye_iter = stmt.query_map([], |row| {
+            Ok(
+                EyeColor {
+                    name: row.get(0)?,
+                    color: row.get(1)?
+                }
+            )
+        })?;
 
-    for color in eye_iter {
-        let color = color.unwrap();
-        eye_colors.push(
-            EyeColor { name: color.name, color: color.color }
-        );
-    }
+        for color in eye_iter {
+            let color = color?;
+            eye_colors.push(
+                EyeColor { name: color.name, color: color.color }
+            );
+        }
 
-    Ok(eye_colors)
+        Ok(eye_colors)
+    })
 }
 
 /// Retrieves face paint data from a SQLite database.
 ///
 /// # Arguments
 ///
-/// * `path` - A string representing the path to the SQLite database file.
+/// * `db` - The cosmetics database.
+/// * `sort` - The order of the list, `None` for the display order, or by name.
 ///
 /// # Returns
 ///
-/// Returns a `Result` containing a vector of `FacePaint` structs or a `rusqlite::Error` if an
+/// Returns a `Result` containing a vector of `FacePaint` structs or a `DatabaseError` if an
 /// error occurs during the database operation.
 ///
 /// # Example
@@ -113,10 +798,10 @@ pub fn get_eye_color(path: &str) -> Result<Vec<EyeColor>, rusqlite::Error> {
 /// ```rust
 /// use your_module::get_facepaints;
 ///
-/// // Provide the path to your SQLite database file
-/// let path = "path/to/your/database.db";
+/// // The database is usually the one managed by Tauri
+/// let db = Database::default();
 ///
-/// match get_facepaints(path) {
+/// match get_facepaints(&db, None) {
 ///     Ok(facepaints) => {
 ///         // Successfully retrieved face paints
 ///         for facepaint in facepaints {
@@ -129,40 +814,51 @@ pub fn get_eye_color(path: &str) -> Result<Vec<EyeColor>, rusqlite::Error> {
 ///     }
 /// }
 /// ```
-pub fn get_facepaints(path: &str) -> Result<Vec<FacePaint>, rusqlite::Error> {
-    let conn = Connection::open(path).unwrap();
-    let mut facepaints: Vec<FacePaint> = vec![];
+pub fn get_facepaints(db: &Database, sort: Option<CosmeticSort>) -> Result<Vec<FacePaint>, DatabaseError> {
+    db.cached("facepaints", &[], sort, || {
+        let conn = db.connection()?;
+        let mut facepaints: Vec<FacePaint> = vec![];
 
-    let mut stmt = conn.prepare("SELECT id, texture_alias FROM FacePaint")?;
-    let facepaint_iter = stmt.query_map([], |row| {
-        Ok(FacePaint {
-            id: row.get(0)?,
-            texture_alias: row.get(1)?,
-        })
-    })?;
-    for facepaint in facepaint_iter {
-        let facepaint = facepaint.unwrap();
-        let buff_facepaints = FacePaint {
-            id: facepaint.id,
-            texture_alias: facepaint.texture_alias
-        };
-        facepaints.push(buff_facepaints);
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, texture_alias FROM FacePaint{}{}",
+            db.filter_rows(&conn, "FacePaint", &[])?,
+            order_by(&conn, "FacePaint", sort, "texture_alias")?,
+        ))?;
+        let facepaint_iter = stmt.query_map([], |row| {
+            Ok(FacePaint {
+                id: row.get(0)?,
+                texture_alias: row.get(1)?,
+            })
+        })?;
+        for facepaint in facepaint_iter {
+            let facepaint = facepaint?;
+            let buff_facepaints = FacePaint {
+                id: facepaint.id,
+                texture_alias: facepaint.texture_alias
+            };
+            facepaints.push(buff_facepaints);
 
-    }
-    Ok(facepaints)
+        }
+        Ok(facepaints)
+    })
 }
 
 /// Retrieves a filtered list of `Hair` based on the specified criteria.
 ///
-/// This function queries the database located at the given `path` and retrieves a list of `Hair`
+/// This function queries the database `db` and retrieves a list of `Hair`
 /// items that match the specified `target_gender`. The function returns a `Result` containing
-/// a `Vec<Hair>` on success, and it may return a `rusqlite::Error` in case of a database error.
+/// a `Vec<Hair>` on success, and it may return a `DatabaseError` in case of a database error.
+///
+/// # Arguments
 ///
+/// * `db` - The cosmetics database.
+/// * `target_gender` - The gender to filter by, `"m"` or `"f"`.
+/// * `sort` - The order of the list, `None` for the display order, or by name.
 ///
 /// # Returns
 ///
 /// A `Result` containing a vector of `Hair` items retrieved from the database on success,
-/// or a `rusqlite::Error` in case of a database error.
+/// or a `DatabaseError` in case of a database error.
 ///
 ///
 /// # Examples
@@ -170,8 +866,8 @@ pub fn get_facepaints(path: &str) -> Result<Vec<FacePaint>, rusqlite::Error> {
 /// ```
 /// use your_module::get_hairs;
 ///
-/// // Assuming a database path and target gender are properly defined
-/// let result = get_hairs("path/to/database.db", "male");
+/// // Assuming the database and target gender are properly defined
+/// let result = get_hairs(&db, "male", None);
 ///
 /// match result {
 ///     Ok(hairs) => {
@@ -186,37 +882,43 @@ pub fn get_facepaints(path: &str) -> Result<Vec<FacePaint>, rusqlite::Error> {
 ///     }
 /// }
 /// ```
-pub fn get_hairs(path: &str, target_gender: &str) -> Result<Vec<Hair>, rusqlite::Error> {
-    let conn = Connection::open(path).unwrap();
-    let mut hairs: Vec<Hair> = vec![];
+pub fn get_hairs(db: &Database, target_gender: &str, sort: Option<CosmeticSort>) -> Result<Vec<Hair>, DatabaseError> {
+    db.cached("hairs", &[target_gender], sort, || {
+        let conn = db.connection()?;
+        let mut hairs: Vec<Hair> = vec![];
 
-    let mut stmt = conn.prepare("SELECT id, addr, name FROM Hair WHERE gender = ?")?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, addr, name, {}, {} FROM Hair{}{}",
+            optional_column(&conn, "Hair", "rarity")?,
+            optional_column(&conn, "Hair", "unlock_flag")?,
+            db.filter_rows(&conn, "Hair", &["gender = ?"])?,
+            order_by(&conn, "Hair", sort, "name")?,
+        ))?;
 
-    let extra_iter = match stmt.query_map([target_gender], |row| {
-        Ok(Hair {
-            id: row.get(0)?,
-            addr: row.get(1)?,
-            name: row.get(2)?
-        })
-    }) {
-    Ok(mapped_rows) => {mapped_rows},
-    Err(e) => {
-        error!("Error iterating Extras due to {:#?}", e);
-        panic!();
-    },
-};
+        let extra_iter = stmt.query_map([target_gender], |row| {
+            Ok(Hair {
+                id: row.get(0)?,
+                addr: row.get(1)?,
+                name: row.get(2)?,
+                rarity: row.get(3)?,
+                unlock_flag: row.get(4)?,
+            })
+        })?;
 
-    for hair in extra_iter {
-        let hair = hair.unwrap();
-        let buff_hair = Hair {
-            id: hair.id,
-            addr: hair.addr,
-            name: hair.name
-        };
-        hairs.push(buff_hair);
+        for hair in extra_iter {
+            let hair = hair?;
+            let buff_hair = Hair {
+                id: hair.id,
+                addr: hair.addr,
+                name: hair.name,
+                rarity: hair.rarity,
+                unlock_flag: hair.unlock_flag,
+            };
+            hairs.push(buff_hair);
 
-    }
-    Ok(hairs)
+        }
+        Ok(hairs)
+    })
 }
 
 
@@ -224,11 +926,12 @@ pub fn get_hairs(path: &str, target_gender: &str) -> Result<Vec<Hair>, rusqlite:
 ///
 /// # Arguments
 ///
-/// * `path` - A string representing the path to the SQLite database file.
+/// * `db` - The cosmetics database.
+/// * `sort` - The order of the list, `None` for the display order, or by name.
 ///
 /// # Returns
 ///
-/// Returns a `Result` containing a vector of `HairColor` structs or a `rusqlite::Error` if an
+/// Returns a `Result` containing a vector of `HairColor` structs or a `DatabaseError` if an
 /// error occurs during the database operation.
 ///
 /// # Example
@@ -236,10 +939,10 @@ pub fn get_hairs(path: &str, target_gender: &str) -> Result<Vec<Hair>, rusqlite:
 /// ```rust
 /// use your_module::get_hair_color;
 ///
-/// // Provide the path to your SQLite database file
-/// let path = "path/to/your/database.db";
+/// // The database is usually the one managed by Tauri
+/// let db = Database::default();
 ///
-/// match get_hair_color(path) {
+/// match get_hair_color(&db, None) {
 ///     Ok(hair_colors) => {
 ///         // Successfully retrieved hair colors
 ///         for color in hair_colors {
@@ -252,57 +955,59 @@ pub fn get_hairs(path: &str, target_gender: &str) -> Result<Vec<Hair>, rusqlite:
 ///     }
 /// }
 /// ```
-pub fn get_hair_color(path: &str) -> Result<Vec<HairColor>, rusqlite::Error> {
-    let conn = Connection::open(path).unwrap();
-    let mut hair_colors:Vec<HairColor> = vec![];
+pub fn get_hair_color(db: &Database, sort: Option<CosmeticSort>) -> Result<Vec<HairColor>, DatabaseError> {
+    db.cached("hair_color", &[], sort, || {
+        let conn = db.connection()?;
+        let mut hair_colors:Vec<HairColor> = vec![];
 
-    let mut stmt = conn.prepare("SELECT name, color FROM Hair_Color")?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT name, color FROM Hair_Color{}{}",
+            db.filter_rows(&conn, "Hair_Color", &[])?,
+            order_by(&conn, "Hair_Color", sort, "name")?,
+        ))?;
 
-    let hair_iter = stmt.query_map([], |row| {
-        Ok(
-            EyeColor {
-                name: row.get(0)?,
-                color: row.get(1)?
-            }
-        )
-    })?;
+        let hair_iter = stmt.query_map([], |row| {
+            Ok(
+                EyeColor {
+                    name: row.get(0)?,
+                    color: row.get(1)?
+                }
+            )
+        })?;
 
-    for color in hair_iter {
-        let color = color.unwrap();
-        hair_colors.push(
-            HairColor { name: color.name, color: color.color }
-        );
-    }
+        for color in hair_iter {
+            let color = color?;
+            hair_colors.push(
+                HairColor { name: color.name, color: color.color }
+            );
+        }
 
-    Ok(hair_colors)
+        Ok(hair_colors)
+    })
 }
 
 /// This function retrieves a filtered list of `Extras` based on the specified gender and species.
 ///
 /// # Arguments
 ///
-/// * `path` - A reference to the path of the SQLite database file.
+/// * `db` - The cosmetics database.
 /// * `target_gender` - A reference to the target gender for filtering.
 /// * `target_species` - A reference to the target species for filtering.
+/// * `sort` - The order of the list, `None` for the display order, or by name.
 ///
 /// # Returns
 ///
-/// * `Result<Vec<Extras>, rusqlite::Error>` - A `Result` containing a `Vec<Extras>` if the operation is successful,
-///   otherwise an `rusqlite::Error` indicating the nature of the failure.
-///
-/// # Panics
-///
-/// This function may panic if there are errors during SQLite database operations, such as preparing statements
-/// or iterating through the query results.
+/// * `Result<Vec<Extras>, DatabaseError>` - A `Result` containing a `Vec<Extras>` if the operation is successful,
+///   otherwise a `DatabaseError` indicating the nature of the failure.
 ///
 /// # Examples
 ///
 /// ```rust
-/// let path = "path/to/database.db";
+/// let db = Database::default();
 /// let gender = "Male";
 /// let species = "Human";
 ///
-/// match get_wings_by_gender_species(path, gender, species) {
+/// match get_wings_by_gender_species(&db, gender, species, None) {
 ///     Ok(result) => {
 ///         // Handle the filtered list of Extras
 ///         println!("Filtered Extras: {:#?}", result);
@@ -313,54 +1018,1123 @@ pub fn get_hair_color(path: &str) -> Result<Vec<HairColor>, rusqlite::Error> {
 ///     }
 /// }
 /// ```
-pub fn get_wings_by_gender_species(
-    path: &str,
+pub fn get_wings_by_gender_species(db: &Database,
     target_gender: &str,
     target_species: &str,
-) -> Result<Vec<Extras>, rusqlite::Error> {
-    let conn = Connection::open(path).unwrap();
-    let mut extras: Vec<Extras> = vec![];
-
-    // Consulta SQL ajustada com cláusulas WHERE para filtrar por gênero e espécie
-    let sql_query = format!(
-        "SELECT id, name, species, gender, addr FROM extras WHERE gender = ? AND species = ?"
-    );
-
-    let mut stmt = match conn.prepare(&sql_query) {
-        Ok(stm) => {stm},
-        Err(e) => {
-            error!("Error ocurred while preparing the statement {}, due to {:?}", &sql_query, e);
-            panic!();
-        },
-    };
+    sort: Option<CosmeticSort>,
+) -> Result<Vec<Extras>, DatabaseError> {
+    let filter = model_filter(CosmeticCategory::Extras, target_gender, Some(target_species));
+    let extras = get_cosmetics(db, &filter, sort)?
+        .into_iter()
+        .map(extras_of)
+        .collect();
+    Ok(extras)
+}
+
+/// Converts `extra`, a cosmetic of the `Extras` category, into the `Extras` it was read from.
+fn extras_of(extra: Cosmetic) -> Extras {
+    Extras {
+        id: extra.id,
+        name: extra.name,
+        species: extra.species.unwrap_or_default(),
+        gender: extra.gender.unwrap_or_default(),
+        addr: extra.value,
+        rarity: extra.rarity,
+        unlock_flag: extra.unlock_flag,
+    }
+}
+
+/// Retrieves the character models of every species from a SQLite database.
+///
+/// These are the playable models the creation form offers, with the display name and the
+/// `PlayerModel` id of each. Only rows matching one of `MODELS` are returned, so the form never
+/// offers a model the rest of the creator doesn't know.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+/// * `sort` - The order of the list, `None` for the display order, or by name.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a vector of `SpeciesModel` structs, one per gender of every
+/// species, or a `DatabaseError` if an error occurs during the database operation.
+pub fn get_species(db: &Database, sort: Option<CosmeticSort>) -> Result<Vec<SpeciesModel>, DatabaseError> {
+    db.cached("species", &[], sort, || {
+        let conn = db.connection()?;
+        let mut species: Vec<SpeciesModel> = vec![];
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT model, species, gender, name FROM Species{}{}",
+            db.filter_rows(&conn, "Species", &[])?,
+            order_by(&conn, "Species", sort, "name")?,
+        ))?;
+        let species_iter = stmt.query_map([], |row| {
+            Ok(SpeciesModel {
+                model: row.get(0)?,
+                species: row.get(1)?,
+                gender: row.get(2)?,
+                name: row.get(3)?,
+            })
+        })?;
+        for model in species_iter {
+            let model = model?;
+            let known = MODELS.iter().any(|known| {
+                known.id == model.model && known.species.as_str() == model.species && known.gender.as_str() == model.gender
+            });
+            if known {
+                species.push(model);
+            } else {
+                warn!("Skipping Species {} of model {}, it doesn't match a known model", model.name, model.model);
+            }
+        }
+        Ok(species)
+    })
+}
+
+/// Retrieves the facial hair available to `gender` and `species` from a SQLite database.
+///
+/// Facial hair is stored with the other extras, this only returns the beards among them.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+/// * `gender` - The gender to filter by, `"m"` or `"f"`.
+/// * `species` - The species to filter by, `"human"` or `"fairy"`.
+/// * `sort` - The order of the list, `None` for the display order, or by name.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a vector of `Extras` structs, empty for models without facial
+/// hair, or a `DatabaseError` if an error occurs during the database operation.
+pub fn get_facial_hair(db: &Database, gender: &str, species: &str, sort: Option<CosmeticSort>) -> Result<Vec<Extras>, DatabaseError> {
+    Ok(get_wings_by_gender_species(db, gender, species, sort)?
+        .into_iter()
+        .filter(|extra| ExtraCategory::of(&extra.addr) == ExtraCategory::Beards)
+        .collect())
+}
+
+/// Retrieves the eyebrow styles available to `gender` from a SQLite database.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+/// * `gender` - The gender to filter by, `"m"` or `"f"`.
+/// * `sort` - The order of the list, `None` for the display order, or by name.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a vector of `Eyebrows` structs or a `DatabaseError` if an
+/// error occurs during the database operation.
+pub fn get_eyebrows(db: &Database, gender: &str, sort: Option<CosmeticSort>) -> Result<Vec<Eyebrows>, DatabaseError> {
+    db.cached("eyebrows", &[gender], sort, || {
+        let conn = db.connection()?;
+        let mut eyebrows: Vec<Eyebrows> = vec![];
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, name, gender, addr FROM Eyebrows{}{}",
+            db.filter_rows(&conn, "Eyebrows", &["gender = ?"])?,
+            order_by(&conn, "Eyebrows", sort, "name")?,
+        ))?;
+        let eyebrows_iter = stmt.query_map([gender], |row| {
+            Ok(Eyebrows {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                gender: row.get(2)?,
+                addr: row.get(3)?,
+            })
+        })?;
+        for style in eyebrows_iter {
+            eyebrows.push(style?);
+        }
+        Ok(eyebrows)
+    })
+}
+
+/// Retrieves the face shapes available to `species` and `gender` from a SQLite database.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+/// * `species` - The species to filter by, `"human"` or `"fairy"`.
+/// * `gender` - The gender to filter by, `"m"` or `"f"`.
+/// * `sort` - The order of the list, `None` for the display order, or by name.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a vector of `FaceShape` structs or a `DatabaseError` if an
+/// error occurs during the database operation.
+pub fn get_face_shapes(db: &Database, species: &str, gender: &str, sort: Option<CosmeticSort>) -> Result<Vec<FaceShape>, DatabaseError> {
+    db.cached("face_shapes", &[species, gender], sort, || {
+        let conn = db.connection()?;
+        let mut face_shapes: Vec<FaceShape> = vec![];
 
-    let extra_iter = match stmt.query_map([target_gender, target_species], |row| {
-            Ok(Extras {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, name, species, gender, addr FROM FaceShapes{}{}",
+            db.filter_rows(&conn, "FaceShapes", &["species = ?", "gender = ?"])?,
+            order_by(&conn, "FaceShapes", sort, "name")?,
+        ))?;
+        let face_shape_iter = stmt.query_map([species, gender], |row| {
+            Ok(FaceShape {
                 id: row.get(0)?,
                 name: row.get(1)?,
                 species: row.get(2)?,
                 gender: row.get(3)?,
-                addr: row.get(4)?
+                addr: row.get(4)?,
             })
-        }) {
-        Ok(mapped_rows) => {mapped_rows},
-        Err(e) => {
-            error!("Error iterating Extras due to {:#?}", e);
-            panic!();
-        },
-    };
+        })?;
+        for face_shape in face_shape_iter {
+            face_shapes.push(face_shape?);
+        }
+        Ok(face_shapes)
+    })
+}
 
-    for extra in extra_iter {
-        let extra = extra.unwrap();
-        let buff_extra = Extras {
-            id: extra.id,
-            name: extra.name,
-            species: extra.species,
-            gender: extra.gender,
-            addr: extra.addr,
-        };
-        extras.push(buff_extra);
-    }
+/// Retrieves the body types and scales allowed for every species from a SQLite database.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a vector of `BodyRange` structs, one per species, or a
+/// `DatabaseError` if an error occurs during the database operation.
+pub fn get_body_ranges(db: &Database) -> Result<Vec<BodyRange>, DatabaseError> {
+    db.cached("body_ranges", &[], None, || {
+        let conn = db.connection()?;
+        let mut ranges: Vec<BodyRange> = vec![];
 
-    Ok(extras)
+        let mut stmt = conn.prepare("SELECT species, min_body_type, max_body_type, min_scale, max_scale FROM BodyTypes")?;
+        let range_iter = stmt.query_map([], |row| {
+            Ok(BodyRange {
+                species: row.get(0)?,
+                min_body_type: row.get(1)?,
+                max_body_type: row.get(2)?,
+                min_scale: row.get(3)?,
+                max_scale: row.get(4)?,
+            })
+        })?;
+        for range in range_iter {
+            ranges.push(range?);
+        }
+        Ok(ranges)
+    })
+}
+
+/// Retrieves the voices available to `species` and `gender` from a SQLite database.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+/// * `species` - The species to filter by, `"human"` or `"fairy"`.
+/// * `gender` - The gender to filter by, `"m"` or `"f"`.
+/// * `sort` - The order of the list, `None` for the display order, or by name.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a vector of `Voice` structs or a `DatabaseError` if an error
+/// occurs during the database operation.
+pub fn get_voices(db: &Database, species: &str, gender: &str, sort: Option<CosmeticSort>) -> Result<Vec<Voice>, DatabaseError> {
+    db.cached("voices", &[species, gender], sort, || {
+        let conn = db.connection()?;
+        let mut voices: Vec<Voice> = vec![];
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, name, species, gender FROM Voices{}{}",
+            db.filter_rows(&conn, "Voices", &["species = ?", "gender = ?"])?,
+            order_by(&conn, "Voices", sort, "name")?,
+        ))?;
+        let voice_iter = stmt.query_map([species, gender], |row| {
+            Ok(Voice {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                species: row.get(2)?,
+                gender: row.get(3)?,
+            })
+        })?;
+        for voice in voice_iter {
+            voices.push(voice?);
+        }
+        Ok(voices)
+    })
+}
+
+/// Retrieves every title a character can hold from a SQLite database.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+/// * `sort` - The order of the list, `None` for the display order, or by name.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a vector of `Title` structs or a `DatabaseError` if an error
+/// occurs during the database operation.
+pub fn get_titles(db: &Database, sort: Option<CosmeticSort>) -> Result<Vec<Title>, DatabaseError> {
+    db.cached("titles", &[], sort, || {
+        let conn = db.connection()?;
+        let mut titles: Vec<Title> = vec![];
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, place, title_name FROM Titles{}{}",
+            db.filter_rows(&conn, "Titles", &[])?,
+            order_by(&conn, "Titles", sort, "title_name")?,
+        ))?;
+        let title_iter = stmt.query_map([], |row| {
+            Ok(Title {
+                id: row.get(0)?,
+                place: row.get(1)?,
+                title_name: row.get(2)?,
+            })
+        })?;
+        for title in title_iter {
+            titles.push(title?);
+        }
+        Ok(titles)
+    })
+}
+
+/// Retrieves every class, or job, a character can start as from a SQLite database.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+/// * `sort` - The order of the list, `None` for the display order, or by name.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a vector of `Class` structs or a `DatabaseError` if an error
+/// occurs during the database operation.
+pub fn get_classes(db: &Database, sort: Option<CosmeticSort>) -> Result<Vec<Class>, DatabaseError> {
+    db.cached("classes", &[], sort, || {
+        let conn = db.connection()?;
+        let mut classes: Vec<Class> = vec![];
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, name, job_name FROM Classes{}{}",
+            db.filter_rows(&conn, "Classes", &[])?,
+            order_by(&conn, "Classes", sort, "name")?,
+        ))?;
+        let class_iter = stmt.query_map([], |row| {
+            Ok(Class {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                job_name: row.get(2)?,
+            })
+        })?;
+        for class in class_iter {
+            classes.push(class?);
+        }
+        Ok(classes)
+    })
+}
+
+/// Retrieves the items that can be equipped in `slot` from a SQLite database.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+/// * `slot` - The id of the equipment slot, e.g. 3 for shirts.
+/// * `sort` - The order of the list, `None` for the display order, or by name.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a vector of `EquipmentItem` structs or a `DatabaseError` if
+/// an error occurs during the database operation.
+pub fn get_equipment(db: &Database, slot: u8, sort: Option<CosmeticSort>) -> Result<Vec<EquipmentItem>, DatabaseError> {
+    db.cached("equipment", &[&slot.to_string()], sort, || {
+        let conn = db.connection()?;
+        let mut items: Vec<EquipmentItem> = vec![];
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT guid, name, slot FROM Equipment{}{}",
+            db.filter_rows(&conn, "Equipment", &["slot = ?"])?,
+            order_by(&conn, "Equipment", sort, "name")?,
+        ))?;
+        let item_iter = stmt.query_map([slot], |row| {
+            Ok(EquipmentItem {
+                guid: row.get(0)?,
+                name: row.get(1)?,
+                slot: row.get(2)?,
+            })
+        })?;
+        for item in item_iter {
+            items.push(item?);
+        }
+        Ok(items)
+    })
+}
+
+/// Retrieves the tails available to `species` from a SQLite database.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+/// * `species` - The species to filter by, e.g. `"human"`.
+/// * `sort` - The order of the list, `None` for the display order, or by name.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a vector of `Tail` structs, empty for species without tails, or
+/// a `DatabaseError` if an error occurs during the database operation.
+pub fn get_tails(db: &Database, species: &str, sort: Option<CosmeticSort>) -> Result<Vec<Tail>, DatabaseError> {
+    db.cached("tails", &[species], sort, || {
+        let conn = db.connection()?;
+        let mut tails: Vec<Tail> = vec![];
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, name, species, addr FROM Tails{}{}",
+            db.filter_rows(&conn, "Tails", &["species = ?"])?,
+            order_by(&conn, "Tails", sort, "name")?,
+        ))?;
+        let tail_iter = stmt.query_map([species], |row| {
+            Ok(Tail {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                species: row.get(2)?,
+                addr: row.get(3)?,
+            })
+        })?;
+        for tail in tail_iter {
+            tails.push(tail?);
+        }
+        Ok(tails)
+    })
+}
+
+/// Retrieves the ear styles available to `species` and `gender` from a SQLite database.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+/// * `species` - The species to filter by, `"human"` or `"fairy"`.
+/// * `gender` - The gender to filter by, `"m"` or `"f"`.
+/// * `sort` - The order of the list, `None` for the display order, or by name.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a vector of `Ears` structs, empty for models without ear
+/// variants, or a `DatabaseError` if an error occurs during the database operation.
+pub fn get_ears(db: &Database, species: &str, gender: &str, sort: Option<CosmeticSort>) -> Result<Vec<Ears>, DatabaseError> {
+    db.cached("ears", &[species, gender], sort, || {
+        let conn = db.connection()?;
+        let mut ears: Vec<Ears> = vec![];
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, name, species, gender, addr FROM Ears{}{}",
+            db.filter_rows(&conn, "Ears", &["species = ?", "gender = ?"])?,
+            order_by(&conn, "Ears", sort, "name")?,
+        ))?;
+        let ears_iter = stmt.query_map([species, gender], |row| {
+            Ok(Ears {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                species: row.get(2)?,
+                gender: row.get(3)?,
+                addr: row.get(4)?,
+            })
+        })?;
+        for style in ears_iter {
+            ears.push(style?);
+        }
+        Ok(ears)
+    })
+}
+
+/// Retrieves the tattoos and body markings available to `species` and `gender` from a SQLite
+/// database.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+/// * `species` - The species to filter by, `"human"` or `"fairy"`.
+/// * `gender` - The gender to filter by, `"m"` or `"f"`.
+/// * `sort` - The order of the list, `None` for the display order, or by name.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a vector of `Marking` structs or a `DatabaseError` if an
+/// error occurs during the database operation.
+pub fn get_markings(db: &Database, species: &str, gender: &str, sort: Option<CosmeticSort>) -> Result<Vec<Marking>, DatabaseError> {
+    db.cached("markings", &[species, gender], sort, || {
+        let conn = db.connection()?;
+        let mut markings: Vec<Marking> = vec![];
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, name, species, gender, addr FROM Markings{}{}",
+            db.filter_rows(&conn, "Markings", &["species = ?", "gender = ?"])?,
+            order_by(&conn, "Markings", sort, "name")?,
+        ))?;
+        let marking_iter = stmt.query_map([species, gender], |row| {
+            Ok(Marking {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                species: row.get(2)?,
+                gender: row.get(3)?,
+                addr: row.get(4)?,
+            })
+        })?;
+        for marking in marking_iter {
+            markings.push(marking?);
+        }
+        Ok(markings)
+    })
+}
+
+/// Retrieves the palette of colors hair highlights can use from a SQLite database.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+/// * `sort` - The order of the list, `None` for the display order, or by name.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a vector of `HairColor` structs or a `DatabaseError` if an
+/// error occurs during the database operation.
+pub fn get_hair_highlight_colors(db: &Database, sort: Option<CosmeticSort>) -> Result<Vec<HairColor>, DatabaseError> {
+    db.cached("hair_highlight_colors", &[], sort, || {
+        let conn = db.connection()?;
+        let mut colors: Vec<HairColor> = vec![];
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT name, color FROM Hair_Highlight_Color{}{}",
+            db.filter_rows(&conn, "Hair_Highlight_Color", &[])?,
+            order_by(&conn, "Hair_Highlight_Color", sort, "name")?,
+        ))?;
+        let color_iter = stmt.query_map([], |row| {
+            Ok(HairColor {
+                name: row.get(0)?,
+                color: row.get(1)?,
+            })
+        })?;
+        for color in color_iter {
+            colors.push(color?);
+        }
+        Ok(colors)
+    })
+}
+
+/// Retrieves the palette of tints pixie wings can use from a SQLite database.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+/// * `sort` - The order of the list, `None` for the display order, or by name.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a vector of `WingColor` structs or a `DatabaseError` if an
+/// error occurs during the database operation.
+pub fn get_wing_colors(db: &Database, sort: Option<CosmeticSort>) -> Result<Vec<WingColor>, DatabaseError> {
+    db.cached("wing_colors", &[], sort, || {
+        let conn = db.connection()?;
+        let mut colors: Vec<WingColor> = vec![];
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT name, color FROM Wing_Color{}{}",
+            db.filter_rows(&conn, "Wing_Color", &[])?,
+            order_by(&conn, "Wing_Color", sort, "name")?,
+        ))?;
+        let color_iter = stmt.query_map([], |row| {
+            Ok(WingColor {
+                name: row.get(0)?,
+                color: row.get(1)?,
+            })
+        })?;
+        for color in color_iter {
+            colors.push(color?);
+        }
+        Ok(colors)
+    })
+}
+
+/// Retrieves the palette of tints face paints can use from a SQLite database.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+/// * `sort` - The order of the list, `None` for the display order, or by name.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a vector of `FacePaintColor` structs or a `DatabaseError` if
+/// an error occurs during the database operation.
+pub fn get_facepaint_colors(db: &Database, sort: Option<CosmeticSort>) -> Result<Vec<FacePaintColor>, DatabaseError> {
+    db.cached("facepaint_colors", &[], sort, || {
+        let conn = db.connection()?;
+        let mut colors: Vec<FacePaintColor> = vec![];
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT name, color FROM FacePaint_Color{}{}",
+            db.filter_rows(&conn, "FacePaint_Color", &[])?,
+            order_by(&conn, "FacePaint_Color", sort, "name")?,
+        ))?;
+        let color_iter = stmt.query_map([], |row| {
+            Ok(FacePaintColor {
+                name: row.get(0)?,
+                color: row.get(1)?,
+            })
+        })?;
+        for color in color_iter {
+            colors.push(color?);
+        }
+        Ok(colors)
+    })
+}
+
+/// Retrieves the skintones available to `species` from a SQLite database.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+/// * `species` - The species to filter by, `"human"` or `"fairy"`.
+/// * `sort` - The order of the list, `None` for the display order, or by name.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a vector of `Skintone` structs or a `DatabaseError` if an
+/// error occurs during the database operation.
+pub fn get_skintones(db: &Database, species: &str, sort: Option<CosmeticSort>) -> Result<Vec<Skintone>, DatabaseError> {
+    db.cached("skintones", &[species], sort, || {
+        let conn = db.connection()?;
+        let mut skintones: Vec<Skintone> = vec![];
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, name, species, addr FROM Skintone{}{}",
+            db.filter_rows(&conn, "Skintone", &["species = ?"])?,
+            order_by(&conn, "Skintone", sort, "name")?,
+        ))?;
+        let skintone_iter = stmt.query_map([species], |row| {
+            Ok(Skintone {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                species: row.get(2)?,
+                addr: row.get(3)?,
+            })
+        })?;
+        for skintone in skintone_iter {
+            skintones.push(skintone?);
+        }
+        Ok(skintones)
+    })
+}
+
+/// Retrieves the named body types available to `species` and `gender` from a SQLite database.
+///
+/// Unlike `get_body_ranges`, which only bounds what the game accepts, these are the body types
+/// offered to the user, each with a name.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+/// * `species` - The species to filter by, `"human"` or `"fairy"`.
+/// * `gender` - The gender to filter by, `"m"` or `"f"`.
+/// * `sort` - The order of the list, `None` for the display order, or by name.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a vector of `BodyType` structs or a `DatabaseError` if an
+/// error occurs during the database operation.
+pub fn get_body_types(db: &Database, species: &str, gender: &str, sort: Option<CosmeticSort>) -> Result<Vec<BodyType>, DatabaseError> {
+    db.cached("body_types", &[species, gender], sort, || {
+        let conn = db.connection()?;
+        let mut body_types: Vec<BodyType> = vec![];
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, name, species, gender FROM Body_Type{}{}",
+            db.filter_rows(&conn, "Body_Type", &["species = ?", "gender = ?"])?,
+            order_by(&conn, "Body_Type", sort, "name")?,
+        ))?;
+        let body_type_iter = stmt.query_map([species, gender], |row| {
+            Ok(BodyType {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                species: row.get(2)?,
+                gender: row.get(3)?,
+            })
+        })?;
+        for body_type in body_type_iter {
+            body_types.push(body_type?);
+        }
+        Ok(body_types)
+    })
+}
+
+/// Retrieves the eye shapes available to `species` from a SQLite database.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+/// * `species` - The species to filter by, `"human"` or `"fairy"`.
+/// * `sort` - The order of the list, `None` for the display order, or by name.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a vector of `EyeShape` structs or a `DatabaseError` if an
+/// error occurs during the database operation.
+pub fn get_eye_shapes(db: &Database, species: &str, sort: Option<CosmeticSort>) -> Result<Vec<EyeShape>, DatabaseError> {
+    db.cached("eye_shapes", &[species], sort, || {
+        let conn = db.connection()?;
+        let mut eye_shapes: Vec<EyeShape> = vec![];
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, name, species, addr FROM Eye_Shape{}{}",
+            db.filter_rows(&conn, "Eye_Shape", &["species = ?"])?,
+            order_by(&conn, "Eye_Shape", sort, "name")?,
+        ))?;
+        let eye_shape_iter = stmt.query_map([species], |row| {
+            Ok(EyeShape {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                species: row.get(2)?,
+                addr: row.get(3)?,
+            })
+        })?;
+        for eye_shape in eye_shape_iter {
+            eye_shapes.push(eye_shape?);
+        }
+        Ok(eye_shapes)
+    })
+}
+
+/// Retrieves the accessories of the slot `slot` available to `species` and `gender` from a
+/// SQLite database.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+/// * `slot` - The accessory slot to filter by, e.g. `"glasses"`.
+/// * `species` - The species to filter by, `"human"` or `"fairy"`.
+/// * `gender` - The gender to filter by, `"m"` or `"f"`.
+/// * `sort` - The order of the list, `None` for the display order, or by name.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a vector of `Accessory` structs or a `DatabaseError` if an
+/// error occurs during the database operation.
+pub fn get_accessories(db: &Database, slot: &str, species: &str, gender: &str, sort: Option<CosmeticSort>) -> Result<Vec<Accessory>, DatabaseError> {
+    db.cached("accessories", &[slot, species, gender], sort, || {
+        let conn = db.connection()?;
+        let mut accessories: Vec<Accessory> = vec![];
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, name, slot, species, gender, addr FROM Accessories{}{}",
+            db.filter_rows(&conn, "Accessories", &["slot = ?", "species = ?", "gender = ?"])?,
+            order_by(&conn, "Accessories", sort, "name")?,
+        ))?;
+        let accessory_iter = stmt.query_map([slot, species, gender], |row| {
+            Ok(Accessory {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                slot: row.get(2)?,
+                species: row.get(3)?,
+                gender: row.get(4)?,
+                addr: row.get(5)?,
+            })
+        })?;
+        for accessory in accessory_iter {
+            accessories.push(accessory?);
+        }
+        Ok(accessories)
+    })
+}
+
+/// Counts the cosmetics of `category` meeting `filter` with a single `COUNT(*)`, without reading
+/// them.
+fn count_cosmetics(db: &Database, category: CosmeticCategory, filter: &CosmeticFilter) -> Result<usize, DatabaseError> {
+    let conn = db.connection()?;
+    let (sql, params) = filter.count_query(db, &conn, category)?;
+    Ok(conn.query_row(&sql, rusqlite::params_from_iter(params), |row| row.get(0))?)
+}
+
+/// Returns the filter selecting the cosmetics of `category` available to `gender`, and to
+/// `species` if there is one.
+fn model_filter(category: CosmeticCategory, gender: &str, species: Option<&str>) -> CosmeticFilter {
+    CosmeticFilter {
+        gender: Some(gender.to_string()),
+        species: species.map(str::to_string),
+        category: Some(category),
+        tags: vec![],
+    }
+}
+
+/// Counts the hairs available to `gender`, so the frontend can page through them.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+/// * `gender` - The gender to filter by, `"m"` or `"f"`.
+///
+/// # Returns
+///
+/// Returns a `Result` containing the number of hairs, or a `DatabaseError` if an error occurs
+/// during the database operation.
+pub fn count_hairs(db: &Database, gender: &str) -> Result<usize, DatabaseError> {
+    count_cosmetics(db, CosmeticCategory::Hair, &model_filter(CosmeticCategory::Hair, gender, None))
+}
+
+/// Counts the extras available to `gender` and `species`, so the frontend can page through them.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+/// * `gender` - The gender to filter by, `"m"` or `"f"`.
+/// * `species` - The species to filter by, `"human"` or `"fairy"`.
+///
+/// # Returns
+///
+/// Returns a `Result` containing the number of extras, or a `DatabaseError` if an error occurs
+/// during the database operation.
+pub fn count_extras(db: &Database, gender: &str, species: &str) -> Result<usize, DatabaseError> {
+    count_cosmetics(db, CosmeticCategory::Extras, &model_filter(CosmeticCategory::Extras, gender, Some(species)))
+}
+
+/// Reads the cosmetics of `category` meeting `filter`, and whose name is like `name_pattern` if
+/// there is one, only those on `page` if there is one.
+fn read_category(db: &Database, conn: &Connection, category: CosmeticCategory, filter: &CosmeticFilter, name_pattern: Option<&str>, sort: Option<CosmeticSort>, page: Option<Page>) -> Result<Vec<Cosmetic>, DatabaseError> {
+    let (sql, params) = filter.query(db, conn, category, name_pattern, sort, page)?;
+    let mut stmt = conn.prepare(&sql)?;
+
+    let cosmetic_iter = stmt.query_map(rusqlite::params_from_iter(params), |row| {
+        Ok(Cosmetic {
+            category,
+            id: row.get(0)?,
+            name: row.get(1)?,
+            value: row.get(2)?,
+            species: row.get(3)?,
+            gender: row.get(4)?,
+            rarity: row.get(5)?,
+            unlock_flag: row.get(6)?,
+        })
+    })?;
+
+    let mut cosmetics: Vec<Cosmetic> = vec![];
+    for cosmetic in cosmetic_iter {
+        cosmetics.push(cosmetic?);
+    }
+    Ok(cosmetics)
+}
+
+/// Reads the cosmetics meeting `filter`, and whose name is like `name_pattern` if there is one,
+/// category by category.
+fn read_cosmetics(db: &Database, filter: &CosmeticFilter, name_pattern: Option<&str>, sort: Option<CosmeticSort>) -> Result<Vec<Cosmetic>, DatabaseError> {
+    let conn = db.connection()?;
+    let mut cosmetics: Vec<Cosmetic> = vec![];
+    for category in CosmeticCategory::ALL.into_iter().filter(|c| filter.category.map_or(true, |category| category == *c)) {
+        cosmetics.extend(read_category(db, &conn, category, filter, name_pattern, sort, None)?);
+    }
+    Ok(cosmetics)
+}
+
+/// Reads the cosmetics of `category` meeting `filter` on `page`, so the database skips the other
+/// rows instead of the whole list being read. Pages are cached like whole lists.
+fn get_cosmetics_page(db: &Database, category: CosmeticCategory, filter: &CosmeticFilter, sort: Option<CosmeticSort>, page: Page) -> Result<Vec<Cosmetic>, DatabaseError> {
+    let gender = filter.gender.as_deref().unwrap_or("*");
+    let species = filter.species.as_deref().unwrap_or("*");
+    let tags = filter.tags.join(",");
+    let (limit, offset) = (page.limit.to_string(), page.offset.to_string());
+    db.cached("cosmetics_page", &[category.as_str(), gender, species, &tags, &limit, &offset], sort, || {
+        let conn = db.connection()?;
+        read_category(db, &conn, category, filter, None, sort, Some(page))
+    })
+}
+
+/// Retrieves the hairs available to `gender` on `page`, reading only the rows of that page.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+/// * `gender` - The gender to filter by, `"m"` or `"f"`.
+/// * `sort` - The order of the list, `None` for the display order, or by name.
+/// * `page` - The rows to return.
+///
+/// # Returns
+///
+/// Returns a `Result` containing the hairs of the page, or a `DatabaseError` if an error occurs
+/// during the database operation.
+pub fn get_hairs_page(db: &Database, gender: &str, sort: Option<CosmeticSort>, page: Page) -> Result<Vec<Hair>, DatabaseError> {
+    let filter = model_filter(CosmeticCategory::Hair, gender, None);
+    let hairs = get_cosmetics_page(db, CosmeticCategory::Hair, &filter, sort, page)?
+        .into_iter()
+        .map(|hair| Hair {
+            id: hair.id,
+            addr: hair.value,
+            name: hair.name,
+            rarity: hair.rarity,
+            unlock_flag: hair.unlock_flag,
+        })
+        .collect();
+    Ok(hairs)
+}
+
+/// Retrieves the extras available to `gender` and `species` on `page`, reading only the rows of
+/// that page.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+/// * `gender` - The gender to filter by, `"m"` or `"f"`.
+/// * `species` - The species to filter by, `"human"` or `"fairy"`.
+/// * `sort` - The order of the list, `None` for the display order, or by name.
+/// * `page` - The rows to return.
+///
+/// # Returns
+///
+/// Returns a `Result` containing the extras of the page, or a `DatabaseError` if an error occurs
+/// during the database operation.
+pub fn get_extras_page(db: &Database, gender: &str, species: &str, sort: Option<CosmeticSort>, page: Page) -> Result<Vec<Extras>, DatabaseError> {
+    let filter = model_filter(CosmeticCategory::Extras, gender, Some(species));
+    let extras = get_cosmetics_page(db, CosmeticCategory::Extras, &filter, sort, page)?
+        .into_iter()
+        .map(extras_of)
+        .collect();
+    Ok(extras)
+}
+
+/// Retrieves the cosmetics meeting every criterion of `filter`, so new combinations of criteria
+/// don't need a query of their own.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+/// * `filter` - The criteria the cosmetics must meet.
+/// * `sort` - The order of the cosmetics of each category, `None` for the display order, or by name.
+///
+/// # Returns
+///
+/// Returns a `Result` containing the cosmetics by category, or a `DatabaseError` if an error
+/// occurs during the database operation, e.g. filtering by tags without a `Cosmetic_Tag` table.
+pub fn get_cosmetics(db: &Database, filter: &CosmeticFilter, sort: Option<CosmeticSort>) -> Result<Vec<Cosmetic>, DatabaseError> {
+    let category = filter.category.map_or("*", CosmeticCategory::as_str);
+    let gender = filter.gender.as_deref().unwrap_or("*");
+    let species = filter.species.as_deref().unwrap_or("*");
+    let tags = filter.tags.join(",");
+    db.cached("cosmetics", &[category, gender, species, &tags], sort, || {
+        read_cosmetics(db, filter, None, sort)
+    })
+}
+
+/// Searches the names of the cosmetics meeting `filter` for `query`, ignoring case, e.g. to find
+/// a hair among thousands of modded ones.
+///
+/// Searches are not cached, as few are made twice.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+/// * `query` - The text to look for anywhere in the names.
+/// * `filter` - The criteria the cosmetics must meet, e.g. the category to search.
+///
+/// # Returns
+///
+/// Returns a `Result` containing the matching cosmetics by category and name, none for an empty
+/// query, or a `DatabaseError` if an error occurs during the database operation.
+pub fn search_cosmetics(db: &Database, query: &str, filter: &CosmeticFilter) -> Result<Vec<Cosmetic>, DatabaseError> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Ok(vec![]);
+    }
+    let pattern = format!("%{}%", query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+    read_cosmetics(db, filter, Some(&pattern), Some(CosmeticSort::Name))
+}
+
+/// Retrieves the extras available to `gender` and `species`, bucketed by `ExtraCategory`, so
+/// every kind of extra can get its own picker.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+/// * `gender` - The gender to filter by, `"m"` or `"f"`.
+/// * `species` - The species to filter by, `"human"` or `"fairy"`.
+/// * `sort` - The order of the list, `None` for the display order, or by name.
+///
+/// # Returns
+///
+/// Returns a `Result` containing the extras of every category the model has, categories without
+/// extras are left out, or a `DatabaseError` if an error occurs during the database operation.
+pub fn get_extras_grouped(db: &Database, gender: &str, species: &str, sort: Option<CosmeticSort>) -> Result<BTreeMap<ExtraCategory, Vec<Extras>>, DatabaseError> {
+    let mut groups: BTreeMap<ExtraCategory, Vec<Extras>> = BTreeMap::new();
+    for extra in get_wings_by_gender_species(db, gender, species, sort)? {
+        groups.entry(ExtraCategory::of(&extra.addr)).or_default().push(extra);
+    }
+    Ok(groups)
+}
+
+/// Retrieves the actual colors of the color ids of the palette `kind` from a SQLite database.
+///
+/// The `Palette` table is optional, databases without it have empty palettes. Rows whose `rgb`
+/// is not a hex color are skipped.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+/// * `kind` - The palette to read.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a vector of `PaletteColor` structs by color id, or a
+/// `DatabaseError` if an error occurs during the database operation.
+pub fn get_palette(db: &Database, kind: PaletteKind) -> Result<Vec<PaletteColor>, DatabaseError> {
+    db.cached("palette", &[kind.as_str()], None, || {
+        let conn = db.connection()?;
+        let mut palette: Vec<PaletteColor> = vec![];
+        if !has_table(&conn, "Palette")? {
+            return Ok(palette);
+        }
+
+        let mut stmt = conn.prepare("SELECT color, rgb FROM Palette WHERE kind = ? ORDER BY color")?;
+        let color_iter = stmt.query_map([kind.as_str()], |row| {
+            Ok((row.get::<_, u32>(0)?, row.get::<_, String>(1)?))
+        })?;
+        for color in color_iter {
+            let (color, rgb) = color?;
+            match PaletteColor::parse(color, &rgb) {
+                Some(parsed) => palette.push(parsed),
+                None => warn!("Skipping color {} of the {} palette, {} is not a hex color", color, kind.as_str(), rgb),
+            }
+        }
+        Ok(palette)
+    })
+}
+
+/// Returns the MIME type of `image` from its first bytes, `None` if it isn't a PNG, JPEG or WebP
+/// image.
+fn image_type(image: &[u8]) -> Option<&'static str> {
+    if image.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if image.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if image.len() >= 12 && image.starts_with(b"RIFF") && &image[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else {
+        None
+    }
+}
+
+/// Retrieves the preview of the cosmetic `id` of `category` from a SQLite database, so pickers
+/// can show images instead of names.
+///
+/// Tables may have a `thumbnail` column holding either the PNG, JPEG or WebP image itself, or the
+/// path of one relative to the folder of the database. Paths leading outside that folder are
+/// ignored. Thumbnails are not cached, as they are large and each is only shown once per picker.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+/// * `category` - The category of the cosmetic.
+/// * `id` - The id of the cosmetic in its table.
+///
+/// # Returns
+///
+/// Returns a `Result` containing the image as a data URI, `None` if the cosmetic has no
+/// thumbnail or its image can't be read, or a `DatabaseError` if an error occurs during the
+/// database operation.
+pub fn get_thumbnail(db: &Database, category: CosmeticCategory, id: usize) -> Result<Option<String>, DatabaseError> {
+    let (table, ..) = category.columns();
+    let conn = db.connection()?;
+    if !has_column(&conn, table, "thumbnail")? {
+        return Ok(None);
+    }
+
+    let mut stmt = conn.prepare(&format!("SELECT thumbnail FROM {} WHERE id = ?", table))?;
+    let thumbnail: Option<Value> = stmt.query_row([id], |row| row.get(0)).optional()?;
+    let image = match thumbnail {
+        Some(Value::Blob(image)) => image,
+        Some(Value::Text(path)) => {
+            // The database may come from a download, so its paths must not leave its folder
+            let dir = Path::new(&conn.path).parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+            let path = match (dir.canonicalize(), dir.join(&path).canonicalize()) {
+                (Ok(dir), Ok(path)) if path.starts_with(&dir) => path,
+                (Ok(_), Ok(_)) => {
+                    warn!("Ignoring the thumbnail {} of {} {}, it is outside the folder of the Database file", path, table, id);
+                    return Ok(None);
+                },
+                (Err(e), _) | (_, Err(e)) => {
+                    warn!("Could not find the thumbnail {} of {} {}, due to {:#?}", path, table, id, e);
+                    return Ok(None);
+                },
+            };
+            match std::fs::read(&path) {
+                Ok(image) => image,
+                Err(e) => {
+                    warn!("Could not read the thumbnail {} of {} {}, due to {:#?}", path.display(), table, id, e);
+                    return Ok(None);
+                },
+            }
+        },
+        _ => return Ok(None),
+    };
+    let mime = match image_type(&image) {
+        Some(mime) => mime,
+        None => {
+            warn!("Ignoring the thumbnail of {} {}, it is not a PNG, JPEG or WebP image", table, id);
+            return Ok(None);
+        },
+    };
+    Ok(Some(format!("data:{};base64,{}", mime, STANDARD.encode(image))))
+}
+
+/// Retrieves the combinations of cosmetics a character can't wear together from a SQLite
+/// database.
+///
+/// The `Constraints` table is optional, databases without it have no constraints.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a vector of `Constraint` structs, or a `DatabaseError` if an
+/// error occurs during the database operation.
+pub fn get_constraints(db: &Database) -> Result<Vec<Constraint>, DatabaseError> {
+    db.cached("constraints", &[], None, || {
+        let conn = db.connection()?;
+        let mut constraints: Vec<Constraint> = vec![];
+        if !has_table(&conn, "Constraints")? {
+            return Ok(constraints);
+        }
+
+        let mut stmt = conn.prepare("SELECT id, field, value, other_field, other_value, reason FROM Constraints")?;
+        let constraint_iter = stmt.query_map([], |row| {
+            Ok(Constraint {
+                id: row.get(0)?,
+                field: row.get(1)?,
+                value: row.get(2)?,
+                other_field: row.get(3)?,
+                other_value: row.get(4)?,
+                reason: row.get(5)?,
+            })
+        })?;
+        for constraint in constraint_iter {
+            constraints.push(constraint?);
+        }
+        Ok(constraints)
+    })
+}
+
+/// Retrieves every cosmetic available to `gender` and `species` at once.
+///
+/// # Arguments
+///
+/// * `db` - The cosmetics database.
+/// * `gender` - The gender to filter by, `"m"` or `"f"`.
+/// * `species` - The species to filter by, `"human"` or `"fairy"`.
+/// * `sort` - The order of the list, `None` for the display order, or by name.
+///
+/// # Returns
+///
+/// Returns a `Result` containing the `CustomizationOptions` of the model, with empty hairs,
+/// skintones and extras if there is no such model, or a `DatabaseError` if any list could not be read.
+pub fn get_customization_options(db: &Database, gender: &str, species: &str, sort: Option<CosmeticSort>) -> Result<CustomizationOptions, DatabaseError> {
+    Ok(CustomizationOptions {
+        hairs: get_hairs(db, gender, sort)?,
+        eye_colors: get_eye_color(db, sort)?,
+        hair_colors: get_hair_color(db, sort)?,
+        facepaints: get_facepaints(db, sort)?,
+        skintones: get_skintones(db, species, sort)?,
+        extras: get_wings_by_gender_species(db, gender, species, sort)?,
+    })
 }
@@ -0,0 +1,172 @@
+use std::{
+    fs,
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+use log::info;
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::api::http::{ClientBuilder, HttpRequestBuilder, ResponseType};
+
+use crate::database::Database;
+use crate::error::DatabaseError;
+use crate::settings::{database_path, get_settings};
+
+/// Manifest of the cosmetics database attached to the latest release, used when the settings
+/// don't name another one.
+pub const DEFAULT_DATABASE_UPDATE_URL: &str =
+    "https://github.com/wolsyn/OSFR-Character-Creator/releases/latest/download/database.json";
+
+/// The published cosmetics database, as described by the manifest at the update URL, e.g.
+/// `{ "version": 3, "url": "https://.../customization.db", "sha256": "9f86d0..." }`.
+#[derive(Debug, Deserialize)]
+struct DatabaseManifest {
+    /// Content version of the published database, matching its `user_version`.
+    version: u32,
+    url: String,
+    /// Checksum of the database file, as hex.
+    sha256: String,
+}
+
+/// Outcome of comparing the local cosmetics database with the published one.
+#[derive(Debug, Serialize)]
+pub struct DatabaseUpdate {
+    pub current_version: u32,
+    pub latest_version: u32,
+    /// Whether the published database is newer than the local one.
+    pub available: bool,
+}
+
+/// Returns the URL of the database manifest, from the settings or the latest release.
+fn update_url() -> String {
+    get_settings()
+        .database_update_url
+        .unwrap_or_else(|| DEFAULT_DATABASE_UPDATE_URL.to_string())
+}
+
+/// Downloads the file at `url`, failing on any status but a success.
+async fn download(url: &str) -> Result<Vec<u8>, Error> {
+    let client = ClientBuilder::new()
+        .max_redirections(5)
+        .build()
+        .map_err(|e| Error::new(ErrorKind::Other, e.to_string()))?;
+    let request = HttpRequestBuilder::new("GET", url)
+        .map_err(|e| Error::new(ErrorKind::InvalidInput, e.to_string()))?
+        .response_type(ResponseType::Binary);
+    let response = client
+        .send(request)
+        .await
+        .map_err(|e| Error::new(ErrorKind::Other, e.to_string()))?
+        .bytes()
+        .await
+        .map_err(|e| Error::new(ErrorKind::Other, e.to_string()))?;
+    if !(200..300).contains(&response.status) {
+        return Err(Error::new(
+            ErrorKind::Other,
+            format!("Downloading {} failed with status {}", url, response.status),
+        ));
+    }
+    Ok(response.data)
+}
+
+/// Downloads and parses the database manifest at the update URL.
+async fn latest_manifest() -> Result<DatabaseManifest, Error> {
+    let url = update_url();
+    let manifest = download(&url).await?;
+    serde_json::from_slice(&manifest)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, format!("Invalid database manifest {}, due to {}", url, e)))
+}
+
+/// Returns the content version of the local database, kept in its `user_version`, or 0 if
+/// there is no database yet so any published one is newer.
+async fn current_version(db: &Database) -> Result<u32, Error> {
+    let version = db
+        .run(|db| {
+            let conn = db.connection()?;
+            Ok(conn.query_row("PRAGMA user_version", [], |row| row.get::<_, u32>(0))?)
+        })
+        .await;
+    match version {
+        Ok(version) => Ok(version),
+        Err(DatabaseError::Missing { .. }) => Ok(0),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Compares the local cosmetics database with the one published at the update URL.
+pub async fn check_database_update(db: &Database) -> Result<DatabaseUpdate, Error> {
+    let manifest = latest_manifest().await?;
+    let current_version = current_version(db).await?;
+    Ok(DatabaseUpdate {
+        current_version,
+        latest_version: manifest.version,
+        available: manifest.version > current_version,
+    })
+}
+
+/// Checks that `file` is an SQLite database of the content version `version`, so a manifest
+/// pointing at the wrong file can't replace the database.
+fn check_downloaded(file: &Path, version: u32) -> Result<(), Error> {
+    let invalid = |e: rusqlite::Error| Error::new(ErrorKind::InvalidData, format!("Downloaded database is unreadable, due to {}", e));
+    let conn = Connection::open_with_flags(file, OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(invalid)?;
+    let downloaded: u32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0)).map_err(invalid)?;
+    if downloaded != version {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Downloaded database is version {} instead of {}", downloaded, version),
+        ));
+    }
+    Ok(())
+}
+
+/// Replaces the local cosmetics database with the published one if it is newer, without
+/// restarting the app.
+///
+/// The download must match the checksum of the manifest and is written next to the database
+/// first, so a failed or corrupt download leaves the database untouched. The previous database
+/// is kept as `{database}.bak`. Once swapped, the cached lists and idle connections are dropped,
+/// so the next queries read the new file, which is migrated like any other on open.
+pub async fn update_database(db: &Database) -> Result<DatabaseUpdate, Error> {
+    let manifest = latest_manifest().await?;
+    let current_version = current_version(db).await?;
+    let mut update = DatabaseUpdate {
+        current_version,
+        latest_version: manifest.version,
+        available: manifest.version > current_version,
+    };
+    if !update.available {
+        return Ok(update);
+    }
+
+    info!("Downloading Database version {} from {}", manifest.version, manifest.url);
+    let data = download(&manifest.url).await?;
+    let checksum = hex::encode(Sha256::digest(&data));
+    if !checksum.eq_ignore_ascii_case(manifest.sha256.trim()) {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Downloaded database checksum {} doesn't match {}", checksum, manifest.sha256),
+        ));
+    }
+
+    let path = database_path();
+    let download_path = format!("{}.download", path);
+    fs::write(&download_path, &data)?;
+    if let Err(e) = check_downloaded(Path::new(&download_path), manifest.version) {
+        fs::remove_file(&download_path)?;
+        return Err(e);
+    }
+
+    if Path::new(&path).is_file() {
+        fs::copy(&path, format!("{}.bak", path))?;
+    }
+    db.invalidate();
+    fs::rename(&download_path, &path)?;
+    db.invalidate();
+    info!("Updated Database file {} from version {} to {}", path, current_version, manifest.version);
+
+    update.current_version = manifest.version;
+    update.available = false;
+    Ok(update)
+}
@@ -0,0 +1,76 @@
+use rusqlite::ErrorCode;
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+
+/// Error of a read from the cosmetics database.
+///
+/// It is returned to the frontend as `{ kind, message }`, with a message meant for the user, so
+/// a missing or busy database is reported instead of bringing the backend down.
+#[derive(Debug)]
+pub enum DatabaseError {
+    /// There is no database file at `path`.
+    Missing { path: String },
+    /// The database is locked by another program, e.g. a database browser with pending changes.
+    Locked,
+    /// The database could be opened but not queried, e.g. because a table is missing.
+    Query(rusqlite::Error),
+    /// The query was cut short before it could return, e.g. because its thread panicked.
+    Interrupted { message: String },
+}
+
+impl DatabaseError {
+    fn kind(&self) -> &'static str {
+        match self {
+            DatabaseError::Missing { .. } => "database_missing",
+            DatabaseError::Locked => "database_locked",
+            DatabaseError::Query(_) | DatabaseError::Interrupted { .. } => "database_error",
+        }
+    }
+}
+
+impl std::fmt::Display for DatabaseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DatabaseError::Missing { path } => write!(f, "The cosmetics database {} could not be found, check the database path in the settings", path),
+            DatabaseError::Locked => write!(f, "The cosmetics database is locked by another program, close it and try again"),
+            DatabaseError::Query(e) => write!(f, "The cosmetics database could not be read, due to {}", e),
+            DatabaseError::Interrupted { message } => write!(f, "The cosmetics database query was interrupted, due to {}", message),
+        }
+    }
+}
+
+impl std::error::Error for DatabaseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DatabaseError::Query(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for DatabaseError {
+    fn from(e: rusqlite::Error) -> Self {
+        match e.sqlite_error_code() {
+            Some(ErrorCode::DatabaseBusy) | Some(ErrorCode::DatabaseLocked) => DatabaseError::Locked,
+            _ => DatabaseError::Query(e),
+        }
+    }
+}
+
+impl From<DatabaseError> for std::io::Error {
+    fn from(e: DatabaseError) -> Self {
+        let kind = match e {
+            DatabaseError::Missing { .. } => std::io::ErrorKind::NotFound,
+            _ => std::io::ErrorKind::Other,
+        };
+        std::io::Error::new(kind, e)
+    }
+}
+
+impl Serialize for DatabaseError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("DatabaseError", 2)?;
+        error.serialize_field("kind", self.kind())?;
+        error.serialize_field("message", &self.to_string())?;
+        error.end()
+    }
+}
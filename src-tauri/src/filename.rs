@@ -0,0 +1,53 @@
+/// Longest file stem, in characters, a character or preset file may have.
+const MAX_FILE_STEM_LENGTH: usize = 100;
+
+/// Characters that can't appear in a file name on at least one supported platform.
+const FORBIDDEN_CHARACTERS: [char; 9] = ['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+/// Device names Windows reserves, which can't be used as a file name whatever their extension.
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL",
+    "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Turns `name` into a file stem that is safe to use on every supported platform.
+///
+/// Path separators, characters Windows forbids and control characters are replaced with `_`,
+/// trailing dots and spaces are removed, and Windows reserved names such as `CON` or `NUL` get a
+/// `_` prefix. Only the file name is affected, the name shown to the user is kept unchanged
+/// inside the JSON.
+///
+/// # Returns
+///
+/// The sanitized stem, or an `std::io::Error` of kind `InvalidInput` if nothing usable is left
+/// of `name` or it is longer than `MAX_FILE_STEM_LENGTH` characters.
+pub fn sanitize_file_stem(name: &str) -> Result<String, std::io::Error> {
+    let escaped: String = name
+        .chars()
+        .map(|c| match FORBIDDEN_CHARACTERS.contains(&c) || c.is_control() {
+            true => '_',
+            false => c,
+        })
+        .collect();
+    let mut stem = escaped.trim_end_matches(['.', ' ']).to_string();
+
+    if stem.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{:?} is not a valid file name", name),
+        ));
+    }
+    if stem.chars().count() > MAX_FILE_STEM_LENGTH {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{} is longer than {} characters", name, MAX_FILE_STEM_LENGTH),
+        ));
+    }
+
+    let device = stem.split('.').next().unwrap_or_default().trim_end();
+    if RESERVED_NAMES.iter().any(|reserved| reserved.eq_ignore_ascii_case(device)) {
+        stem.insert(0, '_');
+    }
+    Ok(stem)
+}
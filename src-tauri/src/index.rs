@@ -0,0 +1,259 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::Path,
+    sync::Mutex,
+};
+
+use chrono::{DateTime, FixedOffset, Local};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::customization::{gender_species_of, new_character_id, ACCOUNT_FIELD, CREATED_AT_FIELD, FAVORITE_FIELD, ID_FIELD, UPDATED_AT_FIELD};
+use crate::settings::{characters_dir, get_settings};
+use crate::utils::write_json_atomically;
+
+/// Name of the manifest kept in the characters directory, next to the character files.
+pub const INDEX_FILE_NAME: &str = "index.json";
+
+/// Serializes every read-modify-write of the index between concurrent commands.
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+/// Summary of a character file, used to let the user pick an existing character.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CharacterSummary {
+    pub id: String,
+    pub file_name: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub gender: String,
+    pub species: String,
+    /// When the character was created, in ISO-8601.
+    pub created_at: String,
+    /// When the character file was last written, in ISO-8601.
+    pub modified_at: String,
+    /// Indexes written before favorites existed lack this field.
+    #[serde(default)]
+    pub favorite: bool,
+    /// Account the character belongs to, if any.
+    #[serde(default)]
+    pub account: Option<String>,
+}
+
+/// Order of the character list.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CharacterOrder {
+    /// Sorted by file name, the order of the index.
+    #[default]
+    FileName,
+    /// Favorites first, each group sorted by file name.
+    FavoritesFirst,
+    /// Most recently written first, so stale characters end up last.
+    RecentlyUpdated,
+}
+
+impl CharacterSummary {
+    /// Returns when the character file was last written, or `None` if the index holds a
+    /// timestamp that is not in ISO-8601.
+    pub fn updated_at(&self) -> Option<DateTime<FixedOffset>> {
+        DateTime::parse_from_rfc3339(&self.modified_at).ok()
+    }
+}
+
+/// Builds the summary of the character `json` stored in the file `file_name`.
+///
+/// The `CreatedAt` and `UpdatedAt` fields of the character are used when present, `created_at`
+/// and `modified_at` are used for character files written before those fields existed.
+fn summary_of(file_name: String, json: &Value, created_at: String, modified_at: String) -> CharacterSummary {
+    let (gender, species) = gender_species_of(json["PlayerModel"].as_u64().unwrap_or_default());
+    CharacterSummary {
+        id: json[ID_FIELD].as_str().unwrap_or_default().to_string(),
+        file_name,
+        first_name: json["FirstName"].as_str().unwrap_or_default().to_string(),
+        last_name: json["LastName"].as_str().unwrap_or_default().to_string(),
+        gender: gender.to_string(),
+        species: species.to_string(),
+        created_at: json[CREATED_AT_FIELD].as_str().map_or(created_at, str::to_string),
+        modified_at: json[UPDATED_AT_FIELD].as_str().map_or(modified_at, str::to_string),
+        favorite: json[FAVORITE_FIELD].as_bool().unwrap_or_default(),
+        account: json[ACCOUNT_FIELD].as_str().map(str::to_string),
+    }
+}
+
+/// Returns the file name of the character file at `file_path`.
+fn file_name_of(file_path: &str) -> String {
+    Path::new(file_path).file_name().unwrap_or_default().to_string_lossy().into_owned()
+}
+
+/// Returns `true` if `path` is a character file, as opposed to the index or temporary files.
+pub fn is_character_file(path: &Path) -> bool {
+    path.extension().map_or(false, |extension| extension == "json")
+        && path.file_name().map_or(false, |file_name| file_name != INDEX_FILE_NAME)
+}
+
+/// Returns the path of the index in the current characters directory.
+fn index_path() -> String {
+    format!("{}/{}", characters_dir(), INDEX_FILE_NAME)
+}
+
+/// Builds the summaries by parsing every character file of the characters directory.
+///
+/// Files that can't be read or parsed are logged and skipped, so a single broken file doesn't
+/// hide the rest of the characters. Character files created before ids existed, or sharing their
+/// id with another file, are given a new id which is written back into the file.
+fn scan_characters() -> Result<Vec<CharacterSummary>, std::io::Error> {
+    let dir = characters_dir();
+    if !Path::new(&dir).exists() {
+        return Ok(vec![]);
+    }
+
+    let mut ids: HashSet<String> = HashSet::new();
+    let mut characters: Vec<CharacterSummary> = vec![];
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if !is_character_file(&path) {
+            continue;
+        }
+
+        let mut json: Value = match fs::read_to_string(&path).map(|content| serde_json::from_str(&content)) {
+            Ok(Ok(json)) => json,
+            _ => {
+                warn!("Skipping unreadable Character file {}", path.display());
+                continue;
+            },
+        };
+        let id = json[ID_FIELD].as_str().unwrap_or_default().to_string();
+        if id.is_empty() || ids.contains(&id) {
+            json[ID_FIELD] = Value::String(new_character_id());
+            info!("Assigning id {} to Character file {}", json[ID_FIELD], path.display());
+            write_json_atomically(&path.to_string_lossy(), &json, get_settings().pretty_json)?;
+        }
+        ids.insert(json[ID_FIELD].as_str().unwrap_or_default().to_string());
+
+        let metadata = fs::metadata(&path)?;
+        let modified_at: DateTime<Local> = metadata.modified()?.into();
+        let created_at: DateTime<Local> = metadata.created().map(Into::into).unwrap_or(modified_at);
+
+        characters.push(summary_of(
+            file_name_of(&path.to_string_lossy()),
+            &json,
+            created_at.to_rfc3339(),
+            modified_at.to_rfc3339(),
+        ));
+    }
+    characters.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+    Ok(characters)
+}
+
+/// Reads the index, rebuilding it from the character files if it is missing or unreadable.
+///
+/// Indexes written before timestamps were kept in ISO-8601 are rebuilt as well.
+fn load_index() -> Result<Vec<CharacterSummary>, std::io::Error> {
+    let index = fs::read_to_string(index_path())
+        .ok()
+        .and_then(|content| serde_json::from_str::<Vec<CharacterSummary>>(&content).ok())
+        .filter(|characters| characters.iter().all(|character| character.updated_at().is_some()));
+
+    match index {
+        Some(characters) => Ok(characters),
+        None => {
+            info!("Rebuilding the Character index");
+            let characters = scan_characters()?;
+            save_index(&characters)?;
+            Ok(characters)
+        },
+    }
+}
+
+/// Writes `characters` as the new index, through a temporary file so it is never half-written.
+fn save_index(characters: &[CharacterSummary]) -> Result<(), std::io::Error> {
+    fs::create_dir_all(characters_dir())?;
+    write_json_atomically(&index_path(), characters, false)
+}
+
+/// Rebuilds the index from the character files, e.g. after they were changed by another program.
+pub fn rebuild_index() -> Result<Vec<CharacterSummary>, std::io::Error> {
+    let _guard = INDEX_LOCK.lock().unwrap();
+    let characters = scan_characters()?;
+    save_index(&characters)?;
+    Ok(characters)
+}
+
+/// Returns the summary of every character, as recorded in the index.
+pub fn list_characters() -> Result<Vec<CharacterSummary>, std::io::Error> {
+    let _guard = INDEX_LOCK.lock().unwrap();
+    load_index()
+}
+
+/// Returns the summary of the character with the id `id`.
+///
+/// If the id is not in the index, the index is rebuilt from the character files once before
+/// giving up with `std::io::ErrorKind::NotFound`, in case files were added by hand.
+pub fn find_character(id: &str) -> Result<CharacterSummary, std::io::Error> {
+    let _guard = INDEX_LOCK.lock().unwrap();
+    if let Some(character) = load_index()?.into_iter().find(|character| character.id == id) {
+        return Ok(character);
+    }
+
+    let characters = scan_characters()?;
+    save_index(&characters)?;
+    characters.into_iter().find(|character| character.id == id).ok_or_else(|| std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        format!("There is no Character with id {}", id),
+    ))
+}
+
+/// Returns the summary of the character stored in the file `file_name`.
+///
+/// If the file is not in the index, or has no id yet, the index is rebuilt from the character
+/// files once, which gives the file an id, before giving up with `std::io::ErrorKind::NotFound`.
+pub fn find_character_file(file_name: &str) -> Result<CharacterSummary, std::io::Error> {
+    let _guard = INDEX_LOCK.lock().unwrap();
+    let indexed = |character: &CharacterSummary| character.file_name == file_name && !character.id.is_empty();
+    if let Some(character) = load_index()?.into_iter().find(indexed) {
+        return Ok(character);
+    }
+
+    let characters = scan_characters()?;
+    save_index(&characters)?;
+    characters.into_iter().find(indexed).ok_or_else(|| std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        format!("There is no Character file {}", file_name),
+    ))
+}
+
+/// Returns the path of the file holding the character with the id `id`.
+pub fn character_file(id: &str) -> Result<String, std::io::Error> {
+    Ok(format!("{}/{}", characters_dir(), find_character(id)?.file_name))
+}
+
+/// Records the character `json`, just written to `file_path`, in the index.
+///
+/// The character is matched by its id, so a character written under a new file name replaces
+/// its previous entry.
+pub fn update_index(file_path: &str, json: &Value) -> Result<(), std::io::Error> {
+    let _guard = INDEX_LOCK.lock().unwrap();
+    let mut characters = load_index()?;
+
+    let id = json[ID_FIELD].as_str().unwrap_or_default();
+    let now = Local::now().to_rfc3339();
+    let created_at = characters.iter()
+        .find(|character| character.id == id)
+        .map_or_else(|| now.clone(), |character| character.created_at.clone());
+
+    characters.retain(|character| character.id != id);
+    characters.push(summary_of(file_name_of(file_path), json, created_at, now));
+    characters.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+    save_index(&characters)
+}
+
+/// Removes the character with the id `id` from the index.
+pub fn remove_from_index(id: &str) -> Result<(), std::io::Error> {
+    let _guard = INDEX_LOCK.lock().unwrap();
+    let mut characters = load_index()?;
+
+    characters.retain(|character| character.id != id);
+    save_index(&characters)
+}
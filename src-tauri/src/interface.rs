@@ -1,154 +1,1705 @@
 
 
 use crate::database::*;
+use crate::database_update::DatabaseUpdate;
+use crate::error::DatabaseError;
 use crate::customization::*;
+use crate::index::{CharacterOrder, CharacterSummary};
+use crate::schema::{check_schema, SchemaIssue};
+use crate::model::{AccessorySlot, EquipmentSlot, Gender, Species};
+use crate::settings::database_path;
+use crate::validation::{AppearanceError, ValidationIssue};
+use crate::watcher::CharacterWatcher;
 use log::error;
+use tauri::{api::dialog::blocking::FileDialogBuilder, AppHandle, Manager, State};
 
 
-const DATABASE_PATH: &str = "database/customization.db";
+/// Event emitted to the frontend whenever a character file is created, renamed or deleted.
+pub const CHARACTERS_CHANGED_EVENT: &str = "characters-changed";
+
+/// Tells the frontend that the character `id` changed, so character lists get refreshed.
+fn notify_characters_changed(app: &AppHandle, id: &str) {
+  if let Err(e) = app.emit_all(CHARACTERS_CHANGED_EVENT, id) {
+    error!("Error ocurred while emitting {}, due to {:#?}", CHARACTERS_CHANGED_EVENT, &e);
+  }
+}
 
 pub mod database_interface {
   use super::*;
   #[tauri::command]
-  pub fn hair_type(gender: String) -> Vec<Hair> {
-    let hairs: Vec<Hair> = match get_hairs(DATABASE_PATH, &gender) {
-        Ok(h) => h,
+  pub async fn hair_type(db: State<'_, Database>, gender: String, sort: Option<CosmeticSort>, page: Option<Page>) -> Result<Vec<Hair>, DatabaseError> {
+    match db.run(move |db| match page {
+      Some(page) => get_hairs_page(db, &gender, sort, page),
+      None => get_hairs(db, &gender, sort),
+    }).await {
+        Ok(hairs) => Ok(hairs),
         Err(e) => {
-          eprintln!("Error while retrieving data from Database file {}, due to {:#?}", DATABASE_PATH, &e);
-          error!("Error while retrieving data from Database file {}, due to {:#?}", DATABASE_PATH, &e);
-          panic!();
+          eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+          error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+          Err(e)
         },
-    };
-    hairs
+    }
   }
 
   #[tauri::command]
-  pub fn hair_color() -> Vec<HairColor>{
-    match get_hair_color(DATABASE_PATH) {
-      Ok(haircolors) => haircolors,
+  pub async fn hair_color(db: State<'_, Database>, sort: Option<CosmeticSort>) -> Result<Vec<HairColor>, DatabaseError> {
+    match db.run(move |db| get_hair_color(db, sort)).await {
+      Ok(haircolors) => Ok(haircolors),
       Err(e) => {
-        eprintln!("Error while retrieving data from Database file {}, due to {:#?}", DATABASE_PATH, &e);
-        error!("Error while retrieving data from Database file {}, due to {:#?}", DATABASE_PATH, &e);
-        panic!();
+        eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+        error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+        Err(e)
       },
     }
   }
 
   #[tauri::command]
-  pub fn eye_color() -> Vec<EyeColor> {
-    match get_eye_color(DATABASE_PATH) {
-        Ok(eyecolors) => eyecolors,
+  pub async fn eye_color(db: State<'_, Database>, sort: Option<CosmeticSort>) -> Result<Vec<EyeColor>, DatabaseError> {
+    match db.run(move |db| get_eye_color(db, sort)).await {
+        Ok(eyecolors) => Ok(eyecolors),
         Err(e) => {
-          eprintln!("Error while retrieving data from Database file {}, due to {:#?}", DATABASE_PATH, &e);
-          error!("Error while retrieving data from Database file {}, due to {:#?}", DATABASE_PATH, &e);
-          panic!();
+          eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+          error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+          Err(e)
         },
     }
   }
 
   #[tauri::command]
-  pub fn model_extras(gender: String, species: String) -> Vec<Extras> {
-    let extras: Vec<Extras> = match get_wings_by_gender_species(DATABASE_PATH, &gender, &species) {
-        Ok(ext) => {ext},
+  pub async fn model_extras(db: State<'_, Database>, gender: String, species: String, sort: Option<CosmeticSort>, page: Option<Page>) -> Result<Vec<Extras>, DatabaseError> {
+    match db.run(move |db| match page {
+      Some(page) => get_extras_page(db, &gender, &species, sort, page),
+      None => get_wings_by_gender_species(db, &gender, &species, sort),
+    }).await {
+        Ok(extras) => Ok(extras),
         Err(e) => {
-          eprintln!("Error while retrieving data from Database file {}, due to {:#?}", DATABASE_PATH, &e);
-          error!("Error while retrieving data from Database file {}, due to {:#?}", DATABASE_PATH, &e);
-          panic!();
+          eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+          error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+          Err(e)
         },
-    };
-  extras
+    }
+  }
+
+#[tauri::command]
+pub async fn hair_count(db: State<'_, Database>, gender: String) -> Result<usize, DatabaseError> {
+  match db.run(move |db| count_hairs(db, &gender)).await {
+    Ok(count) => Ok(count),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+#[tauri::command]
+pub async fn extras_count(db: State<'_, Database>, gender: String, species: String) -> Result<usize, DatabaseError> {
+  match db.run(move |db| count_extras(db, &gender, &species)).await {
+    Ok(count) => Ok(count),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+#[tauri::command]
+pub async fn extras_grouped(db: State<'_, Database>, gender: String, species: String, sort: Option<CosmeticSort>) -> Result<std::collections::BTreeMap<ExtraCategory, Vec<Extras>>, DatabaseError> {
+  match db.run(move |db| get_extras_grouped(db, &gender, &species, sort)).await {
+    Ok(groups) => Ok(groups),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+#[tauri::command]
+pub async fn facial_hair(db: State<'_, Database>, gender: String, species: String, sort: Option<CosmeticSort>) -> Result<Vec<Extras>, DatabaseError> {
+  match db.run(move |db| get_facial_hair(db, &gender, &species, sort)).await {
+    Ok(facial_hair) => Ok(facial_hair),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+#[tauri::command]
+pub async fn eyebrows(db: State<'_, Database>, gender: String, sort: Option<CosmeticSort>) -> Result<Vec<Eyebrows>, DatabaseError> {
+  match db.run(move |db| get_eyebrows(db, &gender, sort)).await {
+    Ok(eyebrows) => Ok(eyebrows),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+#[tauri::command]
+pub async fn face_shapes(db: State<'_, Database>, species: String, gender: String, sort: Option<CosmeticSort>) -> Result<Vec<FaceShape>, DatabaseError> {
+  match db.run(move |db| get_face_shapes(db, &species, &gender, sort)).await {
+    Ok(face_shapes) => Ok(face_shapes),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+#[tauri::command]
+pub async fn body_ranges(db: State<'_, Database>) -> Result<Vec<BodyRange>, DatabaseError> {
+  match db.run(get_body_ranges).await {
+    Ok(ranges) => Ok(ranges),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+#[tauri::command]
+pub async fn body_types(db: State<'_, Database>, species: String, gender: String, sort: Option<CosmeticSort>) -> Result<Vec<BodyType>, DatabaseError> {
+  match db.run(move |db| get_body_types(db, &species, &gender, sort)).await {
+    Ok(body_types) => Ok(body_types),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+#[tauri::command]
+pub async fn voices(db: State<'_, Database>, species: String, gender: String, sort: Option<CosmeticSort>) -> Result<Vec<Voice>, DatabaseError> {
+  match db.run(move |db| get_voices(db, &species, &gender, sort)).await {
+    Ok(voices) => Ok(voices),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+#[tauri::command]
+pub async fn titles(db: State<'_, Database>, sort: Option<CosmeticSort>) -> Result<Vec<Title>, DatabaseError> {
+  match db.run(move |db| get_titles(db, sort)).await {
+    Ok(titles) => Ok(titles),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+#[tauri::command]
+pub async fn classes(db: State<'_, Database>, sort: Option<CosmeticSort>) -> Result<Vec<Class>, DatabaseError> {
+  match db.run(move |db| get_classes(db, sort)).await {
+    Ok(classes) => Ok(classes),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+#[tauri::command]
+pub async fn equipment(db: State<'_, Database>, slot: EquipmentSlot, sort: Option<CosmeticSort>) -> Result<Vec<EquipmentItem>, DatabaseError> {
+  match db.run(move |db| get_equipment(db, slot.id(), sort)).await {
+    Ok(items) => Ok(items),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+#[tauri::command]
+pub async fn eye_shapes(db: State<'_, Database>, species: String, sort: Option<CosmeticSort>) -> Result<Vec<EyeShape>, DatabaseError> {
+  match db.run(move |db| get_eye_shapes(db, &species, sort)).await {
+    Ok(eye_shapes) => Ok(eye_shapes),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+#[tauri::command]
+pub async fn accessories(db: State<'_, Database>, slot: AccessorySlot, species: String, gender: String, sort: Option<CosmeticSort>) -> Result<Vec<Accessory>, DatabaseError> {
+  match db.run(move |db| get_accessories(db, slot.as_str(), &species, &gender, sort)).await {
+    Ok(accessories) => Ok(accessories),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+#[tauri::command]
+pub async fn tails(db: State<'_, Database>, species: String, sort: Option<CosmeticSort>) -> Result<Vec<Tail>, DatabaseError> {
+  match db.run(move |db| get_tails(db, &species, sort)).await {
+    Ok(tails) => Ok(tails),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+#[tauri::command]
+pub async fn ears(db: State<'_, Database>, species: String, gender: String, sort: Option<CosmeticSort>) -> Result<Vec<Ears>, DatabaseError> {
+  match db.run(move |db| get_ears(db, &species, &gender, sort)).await {
+    Ok(ears) => Ok(ears),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+#[tauri::command]
+pub async fn markings(db: State<'_, Database>, species: String, gender: String, sort: Option<CosmeticSort>) -> Result<Vec<Marking>, DatabaseError> {
+  match db.run(move |db| get_markings(db, &species, &gender, sort)).await {
+    Ok(markings) => Ok(markings),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+#[tauri::command]
+pub async fn hair_highlight_color(db: State<'_, Database>, sort: Option<CosmeticSort>) -> Result<Vec<HairColor>, DatabaseError> {
+  match db.run(move |db| get_hair_highlight_colors(db, sort)).await {
+    Ok(colors) => Ok(colors),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+#[tauri::command]
+pub async fn wing_color(db: State<'_, Database>, sort: Option<CosmeticSort>) -> Result<Vec<WingColor>, DatabaseError> {
+  match db.run(move |db| get_wing_colors(db, sort)).await {
+    Ok(colors) => Ok(colors),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+#[tauri::command]
+pub async fn facepaint_color(db: State<'_, Database>, sort: Option<CosmeticSort>) -> Result<Vec<FacePaintColor>, DatabaseError> {
+  match db.run(move |db| get_facepaint_colors(db, sort)).await {
+    Ok(colors) => Ok(colors),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+#[tauri::command]
+pub async fn skintones(db: State<'_, Database>, species: String, sort: Option<CosmeticSort>) -> Result<Vec<Skintone>, DatabaseError> {
+  match db.run(move |db| get_skintones(db, &species, sort)).await {
+    Ok(skintones) => Ok(skintones),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+#[tauri::command]
+pub async fn species(db: State<'_, Database>, sort: Option<CosmeticSort>) -> Result<Vec<SpeciesModel>, DatabaseError> {
+  match db.run(move |db| get_species(db, sort)).await {
+    Ok(species) => Ok(species),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+#[tauri::command]
+pub async fn facepaint(db: State<'_, Database>, sort: Option<CosmeticSort>) -> Result<Vec<FacePaint>, DatabaseError> {
+  match db.run(move |db| get_facepaints(db, sort)).await {
+    Ok(fp) => Ok(fp),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+#[tauri::command]
+pub async fn get_all_customization_options(db: State<'_, Database>, gender: String, species: String, sort: Option<CosmeticSort>) -> Result<CustomizationOptions, DatabaseError> {
+  match db.run(move |db| get_customization_options(db, &gender, &species, sort)).await {
+    Ok(options) => Ok(options),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+#[tauri::command]
+pub async fn get_palette(db: State<'_, Database>, kind: PaletteKind) -> Result<Vec<PaletteColor>, DatabaseError> {
+  match db.run(move |db| crate::database::get_palette(db, kind)).await {
+    Ok(palette) => Ok(palette),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+#[tauri::command]
+pub async fn get_thumbnail(db: State<'_, Database>, category: CosmeticCategory, id: usize) -> Result<Option<String>, DatabaseError> {
+  match db.run(move |db| crate::database::get_thumbnail(db, category, id)).await {
+    Ok(thumbnail) => Ok(thumbnail),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+#[tauri::command]
+pub async fn constraints(db: State<'_, Database>) -> Result<Vec<Constraint>, DatabaseError> {
+  match db.run(get_constraints).await {
+    Ok(constraints) => Ok(constraints),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+#[tauri::command]
+pub async fn cosmetics(db: State<'_, Database>, filter: CosmeticFilter, sort: Option<CosmeticSort>) -> Result<Vec<Cosmetic>, DatabaseError> {
+  match db.run(move |db| get_cosmetics(db, &filter, sort)).await {
+    Ok(cosmetics) => Ok(cosmetics),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+#[tauri::command]
+pub async fn search_cosmetics(db: State<'_, Database>, query: String, category: Option<CosmeticCategory>) -> Result<Vec<Cosmetic>, DatabaseError> {
+  let filter = CosmeticFilter { category, ..Default::default() };
+  match db.run(move |db| crate::database::search_cosmetics(db, &query, &filter)).await {
+    Ok(matches) => Ok(matches),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+/// Checks the schema of the cosmetics database, so the frontend can report a database made for
+/// another version of the app at startup.
+#[tauri::command]
+pub async fn check_database_schema(db: State<'_, Database>) -> Result<Vec<SchemaIssue>, DatabaseError> {
+  match db.run(|_| check_schema()).await {
+    Ok(issues) => Ok(issues),
+    Err(e) => {
+      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error while retrieving data from Database file {}, due to {:#?}", database_path(), &e);
+      Err(e)
+    },
+  }
+}
+
+/// Forgets the cached cosmetic lists, so the next lists are read from the database again.
+#[tauri::command]
+pub fn refresh_cosmetics(db: State<'_, Database>) {
+  db.invalidate();
+}
+
+/// Compares the local cosmetics database with the one published at the update URL of the
+/// settings.
+#[tauri::command]
+pub async fn check_database_update(db: State<'_, Database>) -> Result<DatabaseUpdate, String> {
+  match crate::database_update::check_database_update(&db).await {
+    Ok(update) => Ok(update),
+    Err(e) => {
+      eprintln!("Error ocurred while checking for a newer Database file, due to {:#?}", &e);
+      error!("Error ocurred while checking for a newer Database file, due to {:#?}", &e);
+      Err(e.to_string())
+    },
+  }
 }
 
+/// Downloads the published cosmetics database if it is newer and swaps it in, so the next lists
+/// are read from it without restarting.
 #[tauri::command]
-pub fn facepaint() -> Vec<FacePaint> {
-  let face_paints: Vec<FacePaint> = match get_facepaints(DATABASE_PATH) {
-    Ok(fp) => fp,
+pub async fn update_database(db: State<'_, Database>) -> Result<DatabaseUpdate, String> {
+  match crate::database_update::update_database(&db).await {
+    Ok(update) => Ok(update),
     Err(e) => {
-      eprintln!("Error while retrieving data from Database file {}, due to {:#?}", DATABASE_PATH, &e);
-      error!("Error while retrieving data from Database file {}, due to {:#?}", DATABASE_PATH, &e);
-      panic!();
+      eprintln!("Error ocurred while updating Database file {}, due to {:#?}", database_path(), &e);
+      error!("Error ocurred while updating Database file {}, due to {:#?}", database_path(), &e);
+      Err(e.to_string())
     },
-  };
-  face_paints
+  }
 }
 }
 
 pub mod customization_interface {
   use super::*;
   #[tauri::command]
-  pub async fn new_character(username: String, surname: String){
-    match crate::customization::new_character(&username, &surname).await {
-        Ok(_) => (),
+  pub async fn new_character(app: AppHandle, username: String, surname: Option<String>, template: Option<String>) -> Result<NewCharacter, CreateCharacterError> {
+    let surname = surname.unwrap_or_default();
+    match crate::customization::new_character(&username, &surname, template.as_deref()).await {
+        Ok(NewCharacter::Created { id }) => {
+          notify_characters_changed(&app, &id);
+          Ok(NewCharacter::Created { id })
+        },
+        Ok(existing) => Ok(existing),
         Err(e) => {
-          eprintln!("Error ocurred while reading/writting to Character file named {}{}, due to {:#?}", username, surname, &e);
-          error!("Error ocurred while reading/writting to Character file named {}{}, due to {:#?}", username, surname, &e);
+          eprintln!("Error ocurred while reading/writting to Character file named {}, due to {:#?}", full_name(&username, &surname), &e);
+          error!("Error ocurred while reading/writting to Character file named {}, due to {:#?}", full_name(&username, &surname), &e);
+          Err(e.into())
         },
-    };
+    }
   }
 
+  /// Returns every gender and species combination a character can use, with its model id.
   #[tauri::command]
-  pub async fn set_genderace(username: String, surname: String, genderrace: u8) {
-    match modify_gender(&username, &surname ,genderrace).await {
-      Ok(_) => (),
+  pub fn list_models() -> Vec<crate::model::Model> {
+    crate::model::MODELS.to_vec()
+  }
+
+  #[tauri::command]
+  pub fn generate_name(species: String, gender: String) -> crate::names::GeneratedName {
+    crate::names::generate_name(&species, &gender)
+  }
+
+  #[tauri::command]
+  pub async fn list_characters(order: Option<CharacterOrder>, favorites_only: Option<bool>) -> Vec<CharacterSummary> {
+    match crate::customization::list_characters(order, favorites_only.unwrap_or_default()).await {
+      Ok(characters) => characters,
       Err(e) => {
-        eprintln!("Error ocurred while reading/writting to Character file named {}{}, due to {:#?}", username, surname, &e);
-        error!("Error ocurred while reading/writting to Character file named {}{}, due to {:#?}", username, surname, &e);
+        eprintln!("Error ocurred while listing Character files, due to {:#?}", &e);
+        error!("Error ocurred while listing Character files, due to {:#?}", &e);
+        vec![]
       },
-    };
+    }
   }
 
   #[tauri::command]
-  pub async fn set_eyes(username: String, surname: String, color: usize) { 
-    match modify_eyes(&username, &surname ,color).await {
-      Ok(_) => (),
+  pub async fn character_in_use(id: String) -> Result<bool, String> {
+    match crate::customization::character_in_use(&id).await {
+      Ok(in_use) => Ok(in_use),
       Err(e) => {
-        eprintln!("Error ocurred while reading/writting to Character file named {}{}, due to {:#?}", username, surname, &e);
-        error!("Error ocurred while reading/writting to Character file named {}{}, due to {:#?}", username, surname, &e);
+        eprintln!("Error ocurred while checking the lock of Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while checking the lock of Character {}, due to {:#?}", id, &e);
+        Err(e.to_string())
       },
     }
   }
 
   #[tauri::command]
-  pub async fn set_hair(username: String, surname: String, hairtype: String, haircolor: usize) {
-    match modify_hair(&username, &surname , &hairtype,haircolor).await {
-        Ok(_) => (),
-        Err(e) => {
-          eprintln!("Error ocurred while reading/writting to Character file named {}{}, due to {:#?}", username, surname, &e);
-          error!("Error ocurred while reading/writting to Character file named {}{}, due to {:#?}", username, surname, &e);
-        },
-    };
+  pub async fn toggle_favorite(locks: State<'_, CharacterLocks>, id: String) -> Result<bool, String> {
+    let _guard = locks.lock(&id).await;
+    match crate::customization::toggle_favorite(&id).await {
+      Ok(favorite) => Ok(favorite),
+      Err(e) => {
+        eprintln!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        Err(e.to_string())
+      },
+    }
   }
 
   #[tauri::command]
-  pub async fn set_skintone(username: String, surname: String, newskintone: String) {
-    match modify_skintone(&username, &surname , &newskintone).await {
-      Ok(_) => (),
+  pub async fn set_account(locks: State<'_, CharacterLocks>, id: String, account: String) -> Result<(), String> {
+    let _guard = locks.lock(&id).await;
+    match modify_account(&id, &account).await {
+      Ok(_) => Ok(()),
       Err(e) => {
-        eprintln!("Error ocurred while reading/writting to Character file named {}{}, due to {:#?}", username, surname, &e);
-        error!("Error ocurred while reading/writting to Character file named {}{}, due to {:#?}", username, surname, &e);
+        eprintln!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        Err(e.to_string())
       },
-    };
+    }
   }
 
   #[tauri::command]
-  pub async fn set_extras(username: String, surname: String, wing: String) {
-    match modify_extras(&username, &surname , &wing).await {
-      Ok(_) => (),
+  pub async fn list_characters_by_account() -> Vec<AccountGroup> {
+    match crate::customization::list_characters_by_account().await {
+      Ok(groups) => groups,
       Err(e) => {
-        eprintln!("Error ocurred while reading/writting to Character file named {}{}, due to {:#?}", username, surname, &e);
-        error!("Error ocurred while reading/writting to Character file named {}{}, due to {:#?}", username, surname, &e);
+        eprintln!("Error ocurred while listing Character files, due to {:#?}", &e);
+        error!("Error ocurred while listing Character files, due to {:#?}", &e);
+        vec![]
       },
-    };
+    }
   }
 
   #[tauri::command]
-  pub async fn set_facepaint(username: String, surname: String, facepaint: String) {
-    match modify_facepaint(&username, &surname , &facepaint).await {
-      Ok(_) => (),
+  pub async fn set_notes(locks: State<'_, CharacterLocks>, id: String, notes: String) -> Result<(), String> {
+    let _guard = locks.lock(&id).await;
+    match modify_notes(&id, &notes).await {
+      Ok(_) => Ok(()),
       Err(e) => {
-        eprintln!("Error ocurred while reading/writting to Character file named {}{}, due to {:#?}", username, surname, &e);
-        error!("Error ocurred while reading/writting to Character file named {}{}, due to {:#?}", username, surname, &e);
+        eprintln!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        Err(e.to_string())
       },
-    };
+    }
+  }
+
+  #[tauri::command]
+  pub async fn get_character(id: String) -> Result<serde_json::Value, String> {
+    match crate::customization::get_character(&id).await {
+      Ok(character) => Ok(character),
+      Err(e) => {
+        eprintln!("Error ocurred while reading Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading Character {}, due to {:#?}", id, &e);
+        Err(e.to_string())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn validate_character(db: State<'_, Database>, id: String) -> Result<Vec<ValidationIssue>, String> {
+    match crate::customization::validate_character_file(&id, &db).await {
+      Ok(issues) => Ok(issues),
+      Err(e) => {
+        eprintln!("Error ocurred while validating Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while validating Character {}, due to {:#?}", id, &e);
+        Err(e.to_string())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn repair_character(app: AppHandle, file_name: String) -> Result<CharacterRef, String> {
+    match crate::customization::repair_character(&file_name).await {
+      Ok(repaired) => {
+        notify_characters_changed(&app, &repaired.id);
+        Ok(repaired)
+      },
+      Err(e) => {
+        eprintln!("Error ocurred while repairing Character file {}, due to {:#?}", file_name, &e);
+        error!("Error ocurred while repairing Character file {}, due to {:#?}", file_name, &e);
+        Err(e.to_string())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn delete_character(locks: State<'_, CharacterLocks>, app: AppHandle, id: String) -> Result<(), String> {
+    let _guard = locks.lock(&id).await;
+    match crate::customization::delete_character(&id).await {
+      Ok(_) => {
+        notify_characters_changed(&app, &id);
+        Ok(())
+      },
+      Err(e) => {
+        eprintln!("Error ocurred while deleting Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while deleting Character {}, due to {:#?}", id, &e);
+        Err(e.to_string())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn rename_character(locks: State<'_, CharacterLocks>, app: AppHandle, id: String, new_username: String, new_surname: Option<String>) -> Result<(), String> {
+    let _guard = locks.lock(&id).await;
+    let new_surname = new_surname.unwrap_or_default();
+    match crate::customization::rename_character(&id, &new_username, &new_surname).await {
+      Ok(_) => {
+        notify_characters_changed(&app, &id);
+        Ok(())
+      },
+      Err(e) => {
+        eprintln!("Error ocurred while renaming Character {} to {}, due to {:#?}", id, full_name(&new_username, &new_surname), &e);
+        error!("Error ocurred while renaming Character {} to {}, due to {:#?}", id, full_name(&new_username, &new_surname), &e);
+        Err(e.to_string())
+      },
+    }
   }
-}
\ No newline at end of file
+
+  #[tauri::command]
+  pub async fn set_name(locks: State<'_, CharacterLocks>, app: AppHandle, id: String, first_name: String, surname: Option<String>) -> Result<(), String> {
+    let _guard = locks.lock(&id).await;
+    let surname = surname.unwrap_or_default();
+    match modify_name(&id, &first_name, &surname).await {
+      Ok(_) => {
+        notify_characters_changed(&app, &id);
+        Ok(())
+      },
+      Err(e) => {
+        eprintln!("Error ocurred while renaming Character {} to {}, due to {:#?}", id, full_name(&first_name, &surname), &e);
+        error!("Error ocurred while renaming Character {} to {}, due to {:#?}", id, full_name(&first_name, &surname), &e);
+        Err(e.to_string())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn duplicate_character(locks: State<'_, CharacterLocks>, app: AppHandle, id: String, new_username: String, new_surname: Option<String>) -> Result<String, String> {
+    let _guard = locks.lock(&id).await;
+    let new_surname = new_surname.unwrap_or_default();
+    match crate::customization::duplicate_character(&id, &new_username, &new_surname).await {
+      Ok(new_id) => {
+        notify_characters_changed(&app, &new_id);
+        Ok(new_id)
+      },
+      Err(e) => {
+        eprintln!("Error ocurred while duplicating Character {} as {}, due to {:#?}", id, full_name(&new_username, &new_surname), &e);
+        error!("Error ocurred while duplicating Character {} as {}, due to {:#?}", id, full_name(&new_username, &new_surname), &e);
+        Err(e.to_string())
+      },
+    }
+  }
+
+  /// Asks the user where to save the character and exports it there.
+  ///
+  /// Returns the chosen path, or `None` if the user closed the dialog.
+  #[tauri::command]
+  pub async fn export_character(id: String, pretty: bool) -> Result<Option<String>, String> {
+    let file_name = match crate::index::find_character(&id) {
+      Ok(character) => character.file_name,
+      Err(e) => return Err(e.to_string()),
+    };
+    let destination = match FileDialogBuilder::new()
+      .set_file_name(&file_name)
+      .add_filter("Character", &["json"])
+      .save_file() {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+
+    match crate::customization::export_character(&id, &destination, pretty).await {
+      Ok(_) => Ok(Some(destination.to_string_lossy().into_owned())),
+      Err(e) => {
+        eprintln!("Error ocurred while exporting Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while exporting Character {}, due to {:#?}", id, &e);
+        Err(e.to_string())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn import_character(db: State<'_, Database>, app: AppHandle, path: String) -> Result<CharacterRef, String> {
+    match crate::customization::import_character(std::path::Path::new(&path), &db).await {
+      Ok(imported) => {
+        notify_characters_changed(&app, &imported.id);
+        Ok(imported)
+      },
+      Err(e) => {
+        eprintln!("Error ocurred while importing Character file {}, due to {:#?}", path, &e);
+        error!("Error ocurred while importing Character file {}, due to {:#?}", path, &e);
+        Err(e.to_string())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn diff_characters(id: String, other_id: String) -> Result<Vec<FieldDiff>, String> {
+    match crate::customization::diff_characters(&id, &other_id).await {
+      Ok(diff) => Ok(diff),
+      Err(e) => {
+        eprintln!("Error ocurred while comparing Characters {} and {}, due to {:#?}", id, other_id, &e);
+        error!("Error ocurred while comparing Characters {} and {}, due to {:#?}", id, other_id, &e);
+        Err(e.to_string())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub fn open_session(sessions: State<'_, EditSessions>, id: String) -> Result<serde_json::Value, String> {
+    match sessions.open(&id) {
+      Ok(character) => Ok(character),
+      Err(e) => {
+        eprintln!("Error ocurred while opening a session for Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while opening a session for Character {}, due to {:#?}", id, &e);
+        Err(e.to_string())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub fn set_field(db: State<'_, Database>, sessions: State<'_, EditSessions>, id: String, field: String, value: serde_json::Value) -> Result<(), AppearanceError> {
+    match sessions.set_field(&id, &field, value, &db) {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while setting {} of Character {}, due to {:#?}", field, id, &e);
+        error!("Error ocurred while setting {} of Character {}, due to {:#?}", field, id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn save_session(locks: State<'_, CharacterLocks>, sessions: State<'_, EditSessions>, id: String) -> Result<(), String> {
+    let _guard = locks.lock(&id).await;
+    match sessions.save(&id) {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while saving the session of Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while saving the session of Character {}, due to {:#?}", id, &e);
+        Err(e.to_string())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub fn discard_session(sessions: State<'_, EditSessions>, id: String) -> bool {
+    sessions.discard(&id)
+  }
+
+  #[tauri::command]
+  pub async fn undo_change(locks: State<'_, CharacterLocks>, history: State<'_, EditHistory>, id: String) -> Result<bool, String> {
+    let _guard = locks.lock(&id).await;
+    match history.undo(&id) {
+      Ok(undone) => Ok(undone),
+      Err(e) => {
+        eprintln!("Error ocurred while undoing a change to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while undoing a change to Character {}, due to {:#?}", id, &e);
+        Err(e.to_string())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn redo_change(locks: State<'_, CharacterLocks>, history: State<'_, EditHistory>, id: String) -> Result<bool, String> {
+    let _guard = locks.lock(&id).await;
+    match history.redo(&id) {
+      Ok(redone) => Ok(redone),
+      Err(e) => {
+        eprintln!("Error ocurred while redoing a change to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while redoing a change to Character {}, due to {:#?}", id, &e);
+        Err(e.to_string())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn apply_appearance(locks: State<'_, CharacterLocks>, db: State<'_, Database>, history: State<'_, EditHistory>, id: String, changes: AppearanceDelta) -> Result<(), AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    match history.track(&id, crate::customization::apply_appearance(&id, &changes, &db)).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn reset_field(locks: State<'_, CharacterLocks>, db: State<'_, Database>, history: State<'_, EditHistory>, id: String, field: String, template: Option<String>) -> Result<(), AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    match history.track(&id, crate::customization::reset_field(&id, &field, template.as_deref(), &db)).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while resetting {} of Character {}, due to {:#?}", field, id, &e);
+        error!("Error ocurred while resetting {} of Character {}, due to {:#?}", field, id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn reset_character(locks: State<'_, CharacterLocks>, db: State<'_, Database>, history: State<'_, EditHistory>, id: String, template: Option<String>) -> Result<(), AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    match history.track(&id, crate::customization::reset_character(&id, template.as_deref(), &db)).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while resetting Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while resetting Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn randomize_appearance(locks: State<'_, CharacterLocks>, db: State<'_, Database>, history: State<'_, EditHistory>, id: String, gender: String, species: String) -> Result<AppearanceDelta, AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    match history.track(&id, crate::customization::randomize_appearance(&id, &gender, &species, &db)).await {
+      Ok(changes) => Ok(changes),
+      Err(e) => {
+        eprintln!("Error ocurred while randomizing Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while randomizing Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn set_genderace(locks: State<'_, CharacterLocks>, db: State<'_, Database>, history: State<'_, EditHistory>, id: String, genderrace: u8) -> Result<(), AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    match history.track(&id, modify_gender(&id, genderrace, &db)).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn set_model(locks: State<'_, CharacterLocks>, db: State<'_, Database>, history: State<'_, EditHistory>, id: String, gender: Gender, species: Species) -> Result<(), AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    match history.track(&id, modify_model(&id, gender, species, &db)).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn set_species(locks: State<'_, CharacterLocks>, db: State<'_, Database>, history: State<'_, EditHistory>, id: String, species: Species) -> Result<(), AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    match history.track(&id, modify_species(&id, species, &db)).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn set_eyes(locks: State<'_, CharacterLocks>, db: State<'_, Database>, history: State<'_, EditHistory>, id: String, color: usize) -> Result<(), AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    match history.track(&id, modify_eyes(&id, color, &db)).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn set_hair(locks: State<'_, CharacterLocks>, db: State<'_, Database>, history: State<'_, EditHistory>, id: String, hairtype: String, haircolor: usize) -> Result<(), AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    match history.track(&id, modify_hair(&id, &hairtype, haircolor, &db)).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn set_skintone(locks: State<'_, CharacterLocks>, db: State<'_, Database>, history: State<'_, EditHistory>, id: String, newskintone: String) -> Result<(), AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    match history.track(&id, modify_skintone(&id, &newskintone, &db)).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn set_extras(locks: State<'_, CharacterLocks>, db: State<'_, Database>, history: State<'_, EditHistory>, id: String, wing: String) -> Result<(), AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    match history.track(&id, modify_extras(&id, &wing, &db)).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn set_facial_hair(locks: State<'_, CharacterLocks>, db: State<'_, Database>, history: State<'_, EditHistory>, id: String, beard: String) -> Result<(), AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    match history.track(&id, modify_facial_hair(&id, &beard, &db)).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn set_wings(locks: State<'_, CharacterLocks>, db: State<'_, Database>, history: State<'_, EditHistory>, id: String, wings: String) -> Result<(), AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    match history.track(&id, modify_wings(&id, &wings, &db)).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn set_eyebrows(locks: State<'_, CharacterLocks>, db: State<'_, Database>, history: State<'_, EditHistory>, id: String, style: String, color: usize) -> Result<(), AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    match history.track(&id, modify_eyebrows(&id, &style, color, &db)).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn set_face_shape(locks: State<'_, CharacterLocks>, db: State<'_, Database>, history: State<'_, EditHistory>, id: String, face_shape: String) -> Result<(), AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    match history.track(&id, modify_face_shape(&id, &face_shape, &db)).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn set_body(locks: State<'_, CharacterLocks>, db: State<'_, Database>, history: State<'_, EditHistory>, id: String, body_type: u8, scale: f64) -> Result<(), AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    match history.track(&id, modify_body(&id, body_type, scale, &db)).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn set_voice(locks: State<'_, CharacterLocks>, db: State<'_, Database>, history: State<'_, EditHistory>, id: String, voice: usize) -> Result<(), AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    match history.track(&id, modify_voice(&id, voice, &db)).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn set_title(locks: State<'_, CharacterLocks>, db: State<'_, Database>, id: String, title: u64) -> Result<(), AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    match modify_title(&id, title, &db).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn set_class(locks: State<'_, CharacterLocks>, db: State<'_, Database>, id: String, class: u64) -> Result<(), AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    match modify_class(&id, class, &db).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn set_equipment(locks: State<'_, CharacterLocks>, db: State<'_, Database>, id: String, slot: EquipmentSlot, item: u64) -> Result<(), AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    match modify_equipment(&id, slot, item, &db).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn set_eye_shape(locks: State<'_, CharacterLocks>, db: State<'_, Database>, history: State<'_, EditHistory>, id: String, eye_shape: String) -> Result<(), AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    match history.track(&id, modify_eye_shape(&id, &eye_shape, &db)).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn set_accessory(locks: State<'_, CharacterLocks>, db: State<'_, Database>, history: State<'_, EditHistory>, id: String, slot: AccessorySlot, item: String) -> Result<(), AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    match history.track(&id, modify_accessory(&id, slot, &item, &db)).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn set_tail(locks: State<'_, CharacterLocks>, db: State<'_, Database>, history: State<'_, EditHistory>, id: String, tail: String) -> Result<(), AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    match history.track(&id, modify_tail(&id, &tail, &db)).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn set_ears(locks: State<'_, CharacterLocks>, db: State<'_, Database>, history: State<'_, EditHistory>, id: String, ears: String) -> Result<(), AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    match history.track(&id, modify_ears(&id, &ears, &db)).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn set_markings(locks: State<'_, CharacterLocks>, db: State<'_, Database>, history: State<'_, EditHistory>, id: String, markings: Vec<usize>) -> Result<(), AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    match history.track(&id, modify_markings(&id, &markings, &db)).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn set_hair_highlights(locks: State<'_, CharacterLocks>, db: State<'_, Database>, history: State<'_, EditHistory>, id: String, color: Option<usize>) -> Result<(), AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    match history.track(&id, modify_hair_highlights(&id, color, &db)).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn set_wing_color(locks: State<'_, CharacterLocks>, db: State<'_, Database>, history: State<'_, EditHistory>, id: String, color: u32) -> Result<(), AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    match history.track(&id, modify_wing_color(&id, color, &db)).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn set_facepaint(locks: State<'_, CharacterLocks>, db: State<'_, Database>, history: State<'_, EditHistory>, id: String, facepaint: String, color: Option<u32>, opacity: Option<f64>) -> Result<(), AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    match history.track(&id, modify_facepaint(&id, &facepaint, color, opacity, &db)).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading/writting to Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+}
+
+pub mod backup_interface {
+  use super::*;
+  use crate::backup::Backup;
+
+  #[tauri::command]
+  pub fn list_backups(id: String) -> Vec<Backup> {
+    match crate::backup::list_backups(&id) {
+      Ok(backups) => backups,
+      Err(e) => {
+        eprintln!("Error ocurred while listing backups of Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while listing backups of Character {}, due to {:#?}", id, &e);
+        vec![]
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn restore_backup(locks: State<'_, CharacterLocks>, id: String, backup: String) -> Result<(), String> {
+    let _guard = locks.lock(&id).await;
+    match crate::customization::restore_backup(&id, &backup).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while restoring backup {} of Character {}, due to {:#?}", backup, id, &e);
+        error!("Error ocurred while restoring backup {} of Character {}, due to {:#?}", backup, id, &e);
+        Err(e.to_string())
+      },
+    }
+  }
+}
+
+pub mod version_interface {
+  use super::*;
+  use crate::versions::Version;
+
+  #[tauri::command]
+  pub fn list_versions(id: String) -> Vec<Version> {
+    match crate::versions::list_versions(&id) {
+      Ok(versions) => versions,
+      Err(e) => {
+        eprintln!("Error ocurred while listing versions of Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while listing versions of Character {}, due to {:#?}", id, &e);
+        vec![]
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn restore_version(locks: State<'_, CharacterLocks>, id: String, version: String) -> Result<(), String> {
+    let _guard = locks.lock(&id).await;
+    match crate::customization::restore_version(&id, &version).await {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while restoring version {} of Character {}, due to {:#?}", version, id, &e);
+        error!("Error ocurred while restoring version {} of Character {}, due to {:#?}", version, id, &e);
+        Err(e.to_string())
+      },
+    }
+  }
+}
+
+pub mod audit_interface {
+  use super::*;
+  use crate::audit::Change;
+
+  #[tauri::command]
+  pub fn get_change_history(id: String) -> Result<Vec<Change>, String> {
+    match crate::audit::get_change_history(&id) {
+      Ok(changes) => Ok(changes),
+      Err(e) => {
+        eprintln!("Error ocurred while reading the audit log of Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading the audit log of Character {}, due to {:#?}", id, &e);
+        Err(e.to_string())
+      },
+    }
+  }
+}
+
+pub mod trash_interface {
+  use super::*;
+  use crate::trash::TrashedCharacter;
+
+  #[tauri::command]
+  pub fn list_trashed() -> Vec<TrashedCharacter> {
+    match crate::trash::list_trashed() {
+      Ok(trashed) => trashed,
+      Err(e) => {
+        eprintln!("Error ocurred while listing trashed Characters, due to {:#?}", &e);
+        error!("Error ocurred while listing trashed Characters, due to {:#?}", &e);
+        vec![]
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn restore_trashed(app: AppHandle, file_name: String) -> Result<CharacterRef, String> {
+    match crate::customization::restore_trashed(&file_name).await {
+      Ok(restored) => {
+        notify_characters_changed(&app, &restored.id);
+        Ok(restored)
+      },
+      Err(e) => {
+        eprintln!("Error ocurred while restoring trashed Character {}, due to {:#?}", file_name, &e);
+        error!("Error ocurred while restoring trashed Character {}, due to {:#?}", file_name, &e);
+        Err(e.to_string())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub fn purge_trash() -> Result<usize, String> {
+    match crate::trash::purge_trash() {
+      Ok(purged) => Ok(purged),
+      Err(e) => {
+        eprintln!("Error ocurred while purging the trash, due to {:#?}", &e);
+        error!("Error ocurred while purging the trash, due to {:#?}", &e);
+        Err(e.to_string())
+      },
+    }
+  }
+}
+
+pub mod archive_interface {
+  use super::*;
+  use crate::archive::{ArchiveImportResult, Manifest};
+
+  /// Asks the user where to save the archive and exports every character into it.
+  ///
+  /// Returns the manifest of the archive, or `None` if the user closed the dialog.
+  #[tauri::command]
+  pub async fn export_all_characters() -> Result<Option<Manifest>, String> {
+    let destination = match FileDialogBuilder::new()
+      .set_file_name(&format!("characters_{}.zip", chrono::Local::now().format("%Y%m%d")))
+      .add_filter("Archive", &["zip"])
+      .save_file() {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+
+    match crate::archive::export_all_characters(&destination).await {
+      Ok(manifest) => Ok(Some(manifest)),
+      Err(e) => {
+        eprintln!("Error ocurred while exporting Characters to {}, due to {:#?}", destination.display(), &e);
+        error!("Error ocurred while exporting Characters to {}, due to {:#?}", destination.display(), &e);
+        Err(e.to_string())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn import_archive(db: State<'_, Database>, app: AppHandle, path: String, rename_duplicates: bool) -> Result<Vec<ArchiveImportResult>, String> {
+    match crate::archive::import_archive(std::path::Path::new(&path), &db, rename_duplicates).await {
+      Ok(results) => {
+        for imported in results.iter().filter_map(|result| result.imported.as_ref()) {
+          notify_characters_changed(&app, &imported.id);
+        }
+        Ok(results)
+      },
+      Err(e) => {
+        eprintln!("Error ocurred while importing Characters from {}, due to {:#?}", path, &e);
+        error!("Error ocurred while importing Characters from {}, due to {:#?}", path, &e);
+        Err(e.to_string())
+      },
+    }
+  }
+}
+
+pub mod preset_interface {
+  use super::*;
+  use crate::presets::Preset;
+
+  #[tauri::command]
+  pub fn save_preset(name: String, appearance: AppearanceDelta) -> Result<(), String> {
+    match crate::presets::save_preset(&name, &appearance) {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while saving Preset {}, due to {:#?}", name, &e);
+        error!("Error ocurred while saving Preset {}, due to {:#?}", name, &e);
+        Err(e.to_string())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub fn list_presets() -> Vec<Preset> {
+    match crate::presets::list_presets() {
+      Ok(presets) => presets,
+      Err(e) => {
+        eprintln!("Error ocurred while listing Presets, due to {:#?}", &e);
+        error!("Error ocurred while listing Presets, due to {:#?}", &e);
+        vec![]
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn apply_preset(locks: State<'_, CharacterLocks>, db: State<'_, Database>, history: State<'_, EditHistory>, id: String, preset: String) -> Result<(), AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    let applied = match crate::presets::load_preset(&preset) {
+      Ok(appearance) => history.track(&id, crate::customization::apply_appearance(&id, &appearance, &db)).await,
+      Err(e) => Err(e),
+    };
+
+    match applied {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while applying Preset {} to Character {}, due to {:#?}", preset, id, &e);
+        error!("Error ocurred while applying Preset {} to Character {}, due to {:#?}", preset, id, &e);
+        Err(e.into())
+      },
+    }
+  }
+}
+
+pub mod bulk_interface {
+  use super::*;
+  use crate::bulk::{BulkProgress, BulkRowOutcome, BulkRowResult};
+
+  /// Event emitted after every character of a bulk operation, carrying a `BulkProgress`.
+  pub const BULK_PROGRESS_EVENT: &str = "bulk-progress";
+
+  #[tauri::command]
+  pub async fn bulk_create(db: State<'_, Database>, app: AppHandle, path: String) -> Result<Vec<BulkRowResult>, String> {
+    match crate::bulk::bulk_create(&path, &db).await {
+      Ok(results) => {
+        for result in &results {
+          if let BulkRowOutcome::Created { id } = &result.outcome {
+            notify_characters_changed(&app, id);
+          }
+        }
+        Ok(results)
+      },
+      Err(e) => {
+        eprintln!("Error ocurred while creating Characters from {}, due to {:#?}", path, &e);
+        error!("Error ocurred while creating Characters from {}, due to {:#?}", path, &e);
+        Err(e.to_string())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn apply_preset_bulk(app: AppHandle, locks: State<'_, CharacterLocks>, db: State<'_, Database>, preset: String, ids: Vec<String>) -> Result<Vec<BulkProgress>, String> {
+    let on_progress = |progress: &BulkProgress| {
+      if let Err(e) = app.emit_all(BULK_PROGRESS_EVENT, progress.clone()) {
+        error!("Error ocurred while emitting {}, due to {:#?}", BULK_PROGRESS_EVENT, &e);
+      }
+    };
+    match crate::bulk::apply_preset_bulk(&preset, &ids, &locks, &db, on_progress).await {
+      Ok(results) => Ok(results),
+      Err(e) => {
+        eprintln!("Error ocurred while applying Preset {} to {} Characters, due to {:#?}", preset, ids.len(), &e);
+        error!("Error ocurred while applying Preset {} to {} Characters, due to {:#?}", preset, ids.len(), &e);
+        Err(e.to_string())
+      },
+    }
+  }
+}
+
+pub mod sharing_interface {
+  use super::*;
+  use crate::sharing::{appearance_qr_code, decode_appearance, encode_appearance};
+  use tauri::ClipboardManager;
+
+  fn clipboard_error(e: tauri::Error) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::Other, e.to_string())
+  }
+
+  #[tauri::command]
+  pub async fn export_appearance_code(id: String) -> Result<String, String> {
+    match get_appearance(&id).await {
+      Ok(appearance) => Ok(encode_appearance(&appearance)),
+      Err(e) => {
+        eprintln!("Error ocurred while reading Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while reading Character {}, due to {:#?}", id, &e);
+        Err(e.to_string())
+      },
+    }
+  }
+
+  /// Returns the appearance code of the character rendered as a QR code PNG data URI.
+  #[tauri::command]
+  pub async fn export_appearance_qr(id: String) -> Result<String, String> {
+    match get_appearance(&id).await.and_then(|appearance| appearance_qr_code(&encode_appearance(&appearance))) {
+      Ok(image) => Ok(image),
+      Err(e) => {
+        eprintln!("Error ocurred while rendering appearance QR code of Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while rendering appearance QR code of Character {}, due to {:#?}", id, &e);
+        Err(e.to_string())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn import_appearance_code(locks: State<'_, CharacterLocks>, db: State<'_, Database>, history: State<'_, EditHistory>, id: String, code: String) -> Result<AppearanceDelta, AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    let imported = match decode_appearance(&code) {
+      Ok(appearance) => history.track(&id, crate::customization::apply_appearance(&id, &appearance, &db)).await.map(|_| appearance),
+      Err(e) => Err(e),
+    };
+
+    match imported {
+      Ok(appearance) => Ok(appearance),
+      Err(e) => {
+        eprintln!("Error ocurred while applying appearance code to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while applying appearance code to Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn copy_appearance_to_clipboard(app: AppHandle, id: String) -> Result<(), String> {
+    let copied = get_appearance(&id).await
+      .and_then(|appearance| app.clipboard_manager().write_text(encode_appearance(&appearance)).map_err(clipboard_error));
+
+    match copied {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while copying appearance of Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while copying appearance of Character {}, due to {:#?}", id, &e);
+        Err(e.to_string())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub async fn paste_appearance_from_clipboard(locks: State<'_, CharacterLocks>, db: State<'_, Database>, app: AppHandle, history: State<'_, EditHistory>, id: String) -> Result<AppearanceDelta, AppearanceError> {
+    let _guard = locks.lock(&id).await;
+    let appearance = app.clipboard_manager().read_text()
+      .map_err(clipboard_error)
+      .and_then(|text| decode_appearance(&text.unwrap_or_default()));
+    let pasted = match appearance {
+      Ok(appearance) => history.track(&id, crate::customization::apply_appearance(&id, &appearance, &db)).await.map(|_| appearance),
+      Err(e) => Err(e),
+    };
+
+    match pasted {
+      Ok(appearance) => Ok(appearance),
+      Err(e) => {
+        eprintln!("Error ocurred while pasting appearance to Character {}, due to {:#?}", id, &e);
+        error!("Error ocurred while pasting appearance to Character {}, due to {:#?}", id, &e);
+        Err(e.into())
+      },
+    }
+  }
+}
+
+pub mod template_interface {
+  use super::*;
+  use crate::templates::Template;
+
+  #[tauri::command]
+  pub fn list_templates() -> Vec<Template> {
+    match crate::templates::list_templates() {
+      Ok(templates) => templates,
+      Err(e) => {
+        eprintln!("Error ocurred while listing templates, due to {:#?}", &e);
+        error!("Error ocurred while listing templates, due to {:#?}", &e);
+        vec![]
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub fn create_template_from_character(id: String, name: String) -> Result<(), String> {
+    match crate::templates::create_template_from_character(&id, &name) {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while saving Character {} as template {}, due to {:#?}", id, name, &e);
+        error!("Error ocurred while saving Character {} as template {}, due to {:#?}", id, name, &e);
+        Err(e.to_string())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub fn delete_template(name: String) -> Result<(), String> {
+    match crate::templates::delete_template(&name) {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while deleting template {}, due to {:#?}", name, &e);
+        error!("Error ocurred while deleting template {}, due to {:#?}", name, &e);
+        Err(e.to_string())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub fn restore_fallback_template() -> Result<(), String> {
+    match crate::templates::restore_fallback_template() {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while restoring the Fallback template, due to {:#?}", &e);
+        error!("Error ocurred while restoring the Fallback template, due to {:#?}", &e);
+        Err(e.to_string())
+      },
+    }
+  }
+}
+
+pub mod settings_interface {
+  use super::*;
+  use crate::paths::StorageMode;
+  use crate::settings::{Profile, Settings};
+
+  /// Tells the frontend the characters directory changed from `previous`, if it did, and watches
+  /// the new one.
+  fn characters_dir_changed(app: &AppHandle, previous: &str) {
+    let dir = crate::settings::characters_dir();
+    if dir == previous {
+      return;
+    }
+    notify_characters_changed(app, &dir);
+    if let Err(e) = migrate_file_names() {
+      error!("Error ocurred while renaming the Character files of {}, due to {:#?}", dir, &e);
+    }
+    if let Err(e) = app.state::<CharacterWatcher>().watch(app.clone()) {
+      error!("Error ocurred while watching the characters directory {}, due to {:#?}", dir, &e);
+    }
+  }
+
+  #[tauri::command]
+  pub fn get_settings() -> Settings {
+    crate::settings::get_settings()
+  }
+
+  #[tauri::command]
+  pub fn update_settings(app: AppHandle, settings: Settings) -> Result<Settings, String> {
+    let previous = crate::settings::characters_dir();
+    match crate::settings::update_settings(&settings) {
+      Ok(_) => {
+        characters_dir_changed(&app, &previous);
+        Ok(settings)
+      },
+      Err(e) => {
+        eprintln!("Error ocurred while updating settings to {:?}, due to {:#?}", settings, &e);
+        error!("Error ocurred while updating settings to {:?}, due to {:#?}", settings, &e);
+        Err(e.to_string())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub fn list_profiles() -> Vec<Profile> {
+    crate::settings::list_profiles()
+  }
+
+  #[tauri::command]
+  pub fn switch_profile(app: AppHandle, name: Option<String>) -> Result<(), String> {
+    let previous = crate::settings::characters_dir();
+    match crate::settings::switch_profile(name.as_deref()) {
+      Ok(_) => {
+        characters_dir_changed(&app, &previous);
+        Ok(())
+      },
+      Err(e) => {
+        eprintln!("Error ocurred while switching to profile {:?}, due to {:#?}", name, &e);
+        error!("Error ocurred while switching to profile {:?}, due to {:#?}", name, &e);
+        Err(e.to_string())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub fn get_storage_mode() -> StorageMode {
+    crate::paths::storage_mode()
+  }
+
+  /// Records the storage mode to use once the app is restarted.
+  #[tauri::command]
+  pub fn set_storage_mode(mode: StorageMode) -> Result<(), String> {
+    match crate::paths::set_storage_mode(mode) {
+      Ok(_) => Ok(()),
+      Err(e) => {
+        eprintln!("Error ocurred while setting the storage mode to {:?}, due to {:#?}", mode, &e);
+        error!("Error ocurred while setting the storage mode to {:?}, due to {:#?}", mode, &e);
+        Err(e.to_string())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub fn get_characters_dir() -> String {
+    crate::settings::characters_dir()
+  }
+
+  /// Asks the user for a folder and makes it the characters directory.
+  ///
+  /// Returns the chosen folder, or `None` if the user closed the dialog.
+  #[tauri::command]
+  pub async fn choose_characters_dir(app: AppHandle) -> Result<Option<String>, String> {
+    let dir = match FileDialogBuilder::new()
+      .set_directory(crate::settings::characters_dir())
+      .pick_folder() {
+        Some(path) => path.to_string_lossy().into_owned(),
+        None => return Ok(None),
+    };
+
+    let previous = crate::settings::characters_dir();
+    match crate::settings::set_characters_dir(Some(&dir)) {
+      Ok(_) => {
+        characters_dir_changed(&app, &previous);
+        Ok(Some(dir))
+      },
+      Err(e) => {
+        eprintln!("Error ocurred while setting the characters directory to {}, due to {:#?}", dir, &e);
+        error!("Error ocurred while setting the characters directory to {}, due to {:#?}", dir, &e);
+        Err(e.to_string())
+      },
+    }
+  }
+
+  #[tauri::command]
+  pub fn reset_characters_dir(app: AppHandle) -> Result<String, String> {
+    let previous = crate::settings::characters_dir();
+    match crate::settings::set_characters_dir(None) {
+      Ok(_) => {
+        characters_dir_changed(&app, &previous);
+        Ok(crate::settings::characters_dir())
+      },
+      Err(e) => {
+        eprintln!("Error ocurred while resetting the characters directory, due to {:#?}", &e);
+        error!("Error ocurred while resetting the characters directory, due to {:#?}", &e);
+        Err(e.to_string())
+      },
+    }
+  }
+}
@@ -0,0 +1,46 @@
+use std::{io::ErrorKind, path::Path};
+
+use log::warn;
+
+/// Extension of the lock file a running OSFR server creates next to every character file it has
+/// loaded, e.g. `JohnDoe.json.lock`, and removes once the character is unloaded.
+const LOCK_EXTENSION: &str = "lock";
+
+/// Error of a write refused because the character file is loaded by a running server, which
+/// would overwrite the change or be confused by it.
+#[derive(Debug, Clone)]
+pub struct CharacterInUse {
+    pub file_name: String,
+}
+
+impl std::fmt::Display for CharacterInUse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Character file {} is in use by a running server", self.file_name)
+    }
+}
+
+impl std::error::Error for CharacterInUse {}
+
+/// Returns the path of the lock file of the character file at `file_path`.
+fn lock_path(file_path: &str) -> String {
+    format!("{}.{}", file_path, LOCK_EXTENSION)
+}
+
+/// Returns `true` if the character file at `file_path` is loaded by a running server.
+///
+/// A server that crashed may leave its lock files behind, they can be removed by hand once no
+/// server is running.
+pub fn is_locked(file_path: &str) -> bool {
+    Path::new(&lock_path(file_path)).exists()
+}
+
+/// Fails with an error wrapping `CharacterInUse` if the character file at `file_path` is loaded
+/// by a running server.
+pub fn ensure_unlocked(file_path: &str) -> Result<(), std::io::Error> {
+    if !is_locked(file_path) {
+        return Ok(());
+    }
+    warn!("Refusing to write {}, it is locked by a running server", file_path);
+    let file_name = Path::new(file_path).file_name().unwrap_or_default().to_string_lossy().into_owned();
+    Err(std::io::Error::new(ErrorKind::PermissionDenied, CharacterInUse { file_name }))
+}
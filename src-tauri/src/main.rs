@@ -2,22 +2,50 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 mod utils;
 mod database;
+mod database_update;
+mod error;
+mod schema;
 mod customization;
 mod interface;
+mod backup;
+mod validation;
+mod archive;
+mod presets;
+mod names;
+mod sharing;
+mod versions;
+mod trash;
+mod index;
+mod filename;
+mod settings;
+mod paths;
+mod templates;
+mod migration;
+mod repair;
+mod model;
+mod audit;
+mod watcher;
+mod lock;
+mod bulk;
 
 use std::{io::Write, path::Path, fs::{create_dir_all, File}};
 use utils::*;
-use log::LevelFilter;
+use log::{error, LevelFilter};
 use chrono::Local;
 use env_logger::Builder;
 
-use interface::{database_interface::*, customization_interface::*};
+use customization::{flush_pending_writes, CharacterLocks, EditHistory, EditSessions};
+use database::Database;
+use tauri::Manager;
+use watcher::CharacterWatcher;
+use interface::{database_interface::*, customization_interface::*, backup_interface::*, version_interface::*, audit_interface::*, trash_interface::*, archive_interface::*, preset_interface::*, bulk_interface::*, sharing_interface::*, template_interface::*, settings_interface::*};
 
 fn main() {
-    if !Path::new("logs").exists() {
-        create_dir_all("logs").unwrap();
+    let logs_dir = paths::data_path("logs");
+    if !Path::new(&logs_dir).exists() {
+        create_dir_all(&logs_dir).unwrap();
     }
-    let log_file = File::create("logs/log.txt").unwrap();
+    let log_file = File::create(Path::new(&logs_dir).join("log.txt")).unwrap();
   Builder::new()
       .format(|buf, record| {
           writeln!(
@@ -34,6 +62,23 @@ fn main() {
       .init();
 
   tauri::Builder::default()
+    .manage(Database::default())
+    .manage(EditHistory::default())
+    .manage(EditSessions::default())
+    .manage(CharacterLocks::default())
+    .manage(CharacterWatcher::default())
+    .setup(|app| {
+      if let Err(e) = customization::migrate_file_names() {
+        error!("Error ocurred while renaming the Character files, due to {:#?}", &e);
+      }
+      if let Err(e) = app.state::<CharacterWatcher>().watch(app.handle()) {
+        error!("Error ocurred while watching the characters directory, due to {:#?}", &e);
+      }
+      if let Err(e) = schema::check_schema() {
+        error!("Error ocurred while checking the schema of the Database file, due to {:#?}", &e);
+      }
+      Ok(())
+    })
     .invoke_handler(tauri::generate_handler![
         open_explorer,
         eye_color,
@@ -41,14 +86,130 @@ fn main() {
         hair_color,
         model_extras, 
         facepaint, 
+        species,
+        hair_count,
+        extras_count,
+        extras_grouped,
+        facial_hair,
+        eyebrows,
+        face_shapes,
+        body_ranges,
+        body_types,
+        voices,
+        titles,
+        classes,
+        equipment,
+        eye_shapes,
+        accessories,
+        tails,
+        ears,
+        markings,
+        hair_highlight_color,
+        wing_color,
+        facepaint_color,
+        skintones,
+        get_all_customization_options,
+        get_palette,
+        get_thumbnail,
+        constraints,
+        cosmetics,
+        search_cosmetics,
+        check_database_schema,
+        refresh_cosmetics,
+        check_database_update,
+        update_database,
         new_character,
+        generate_name,
+        list_models,
+        list_characters,
+        get_character,
+        set_account,
+        list_characters_by_account,
+        set_notes,
+        toggle_favorite,
+        character_in_use,
+        validate_character,
+        repair_character,
+        delete_character,
+        rename_character,
+        set_name,
+        duplicate_character,
+        export_character,
+        import_character,
+        diff_characters,
         set_genderace,
+        set_model,
+        set_species,
         set_facepaint,
         set_eyes,
         set_hair,
         set_skintone,
-        set_extras
+        set_extras,
+        set_facial_hair,
+        set_eyebrows,
+        set_face_shape,
+        set_body,
+        set_voice,
+        set_title,
+        set_class,
+        set_equipment,
+        set_eye_shape,
+        set_accessory,
+        set_tail,
+        set_ears,
+        set_markings,
+        set_hair_highlights,
+        set_wing_color,
+        set_wings,
+        apply_appearance,
+        reset_field,
+        reset_character,
+        randomize_appearance,
+        open_session,
+        set_field,
+        save_session,
+        discard_session,
+        undo_change,
+        redo_change,
+        list_backups,
+        restore_backup,
+        list_versions,
+        restore_version,
+        get_change_history,
+        list_trashed,
+        restore_trashed,
+        purge_trash,
+        export_all_characters,
+        import_archive,
+        save_preset,
+        list_presets,
+        apply_preset,
+        bulk_create,
+        apply_preset_bulk,
+        export_appearance_code,
+        export_appearance_qr,
+        import_appearance_code,
+        copy_appearance_to_clipboard,
+        paste_appearance_from_clipboard,
+        list_templates,
+        create_template_from_character,
+        delete_template,
+        restore_fallback_template,
+        get_settings,
+        update_settings,
+        list_profiles,
+        switch_profile,
+        get_storage_mode,
+        set_storage_mode,
+        get_characters_dir,
+        choose_characters_dir,
+        reset_characters_dir
       ])
-    .run(tauri::generate_context!())
-    .expect("error while running tauri application");
+    .build(tauri::generate_context!())
+    .expect("error while running tauri application")
+    .run(|_app, event| {
+      if let tauri::RunEvent::Exit = event {
+        flush_pending_writes();
+      }
+    });
 }
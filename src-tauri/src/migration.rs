@@ -0,0 +1,95 @@
+use log::{info, warn};
+use serde_json::{Map, Value};
+
+use crate::customization::{gender_species_of, BEARD_FIELD, EXTRAS_FIELD, WINGS_FIELD};
+use crate::templates::load_fallback_template;
+
+/// Field of a character file holding the version of the format it was written with.
+pub const SCHEMA_VERSION_FIELD: &str = "SchemaVersion";
+
+/// Version of the character format written by this build. Files without a `SchemaVersion`
+/// predate versioning and are treated as version 0.
+pub const CURRENT_SCHEMA_VERSION: u64 = 2;
+
+/// Keys that were renamed, as the schema version introducing the new name, the old key and the
+/// new key. Files older than that version get the old key moved to the new one.
+const RENAMED_KEYS: &[(u64, &str, &str)] = &[];
+
+/// Upgrades that can't be expressed as a rename, as the schema version introducing them and the
+/// function applying them. Files older than that version are passed through the function.
+const UPGRADES: &[(u64, fn(&mut Map<String, Value>))] = &[(2, split_extras)];
+
+/// Version 2 keeps the beard and the wings of a character in separate fields, so a character
+/// changing species doesn't lose them. The extra of older files is moved to the field matching
+/// their model.
+fn split_extras(character: &mut Map<String, Value>) {
+    let model = character.get("PlayerModel").and_then(Value::as_u64).unwrap_or_default();
+    let field = match gender_species_of(model) {
+        ("m", "human") => BEARD_FIELD,
+        (_, "fairy") => WINGS_FIELD,
+        _ => return,
+    };
+    if let Some(extra) = character.get(EXTRAS_FIELD).cloned() {
+        character.insert(field.to_string(), extra);
+    }
+}
+
+/// Returns the schema version the character `json` was written with.
+pub fn schema_version_of(json: &Value) -> u64 {
+    json[SCHEMA_VERSION_FIELD].as_u64().unwrap_or(0)
+}
+
+/// Renames `old_key` of `character` to `new_key` in place, so the key keeps its position in the
+/// file. If `new_key` is already present, its value wins and `old_key` is dropped.
+fn rename_key(character: &mut Map<String, Value>, old_key: &str, new_key: &str) {
+    let keep_new = character.contains_key(new_key);
+    *character = std::mem::take(character)
+        .into_iter()
+        .filter(|(key, _)| !(keep_new && key == old_key))
+        .map(|(key, value)| if key == old_key { (new_key.to_string(), value) } else { (key, value) })
+        .collect();
+}
+
+/// Upgrades the character `json` to `CURRENT_SCHEMA_VERSION`.
+///
+/// Keys moved since the file was written are renamed, the `UPGRADES` newer than the file are
+/// applied, keys the file lacks are added with the value of the current template, and `SchemaVersion` is set. Files written by a newer build are
+/// left untouched.
+///
+/// # Returns
+///
+/// `true` if the character was changed and should be saved.
+pub fn migrate_character(json: &mut Value) -> Result<bool, std::io::Error> {
+    let version = schema_version_of(json);
+    if version >= CURRENT_SCHEMA_VERSION {
+        if version > CURRENT_SCHEMA_VERSION {
+            warn!("Character file has schema version {}, newer than {}", version, CURRENT_SCHEMA_VERSION);
+        }
+        return Ok(false);
+    }
+    let character = match json.as_object_mut() {
+        Some(character) => character,
+        None => return Ok(false),
+    };
+
+    for (introduced_in, old_key, new_key) in RENAMED_KEYS {
+        if version < *introduced_in && character.contains_key(*old_key) {
+            rename_key(character, old_key, new_key);
+        }
+    }
+    for (introduced_in, upgrade) in UPGRADES {
+        if version < *introduced_in {
+            upgrade(character);
+        }
+    }
+
+    if let Value::Object(template) = load_fallback_template()? {
+        for (key, value) in template {
+            character.entry(key).or_insert(value);
+        }
+    }
+
+    character.insert(SCHEMA_VERSION_FIELD.to_string(), Value::from(CURRENT_SCHEMA_VERSION));
+    info!("Migrated Character from schema version {} to {}", version, CURRENT_SCHEMA_VERSION);
+    Ok(true)
+}
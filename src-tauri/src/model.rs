@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+
+/// Gender of a character, serialized the way the database stores it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Gender {
+    #[serde(rename = "m")]
+    Male,
+    #[serde(rename = "f")]
+    Female,
+}
+
+/// Species of a character, serialized the way the database stores it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Species {
+    #[serde(rename = "human")]
+    Human,
+    #[serde(rename = "fairy")]
+    Fairy,
+}
+
+impl Gender {
+    /// Returns the gender as stored in the database, `"m"` or `"f"`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Gender::Male => "m",
+            Gender::Female => "f",
+        }
+    }
+
+    /// Parses a gender as stored in the database.
+    pub fn parse(gender: &str) -> Option<Gender> {
+        match gender {
+            "m" => Some(Gender::Male),
+            "f" => Some(Gender::Female),
+            _ => None,
+        }
+    }
+}
+
+impl Species {
+    /// Returns the species as stored in the database, `"human"` or `"fairy"`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Species::Human => "human",
+            Species::Fairy => "fairy",
+        }
+    }
+
+    /// Parses a species as stored in the database.
+    pub fn parse(species: &str) -> Option<Species> {
+        match species {
+            "human" => Some(Species::Human),
+            "fairy" => Some(Species::Fairy),
+            _ => None,
+        }
+    }
+}
+
+/// A character model the game can render, as written to `PlayerModel`.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Model {
+    pub id: u8,
+    pub gender: Gender,
+    pub species: Species,
+    pub name: &'static str,
+}
+
+/// Every model a character can use.
+pub const MODELS: [Model; 4] = [
+    Model { id: 60, gender: Gender::Female, species: Species::Human, name: "Female Human" },
+    Model { id: 1, gender: Gender::Male, species: Species::Human, name: "Male Human" },
+    Model { id: 61, gender: Gender::Female, species: Species::Fairy, name: "Female Pixie" },
+    Model { id: 2, gender: Gender::Male, species: Species::Fairy, name: "Male Pixie" },
+];
+
+/// Returns the model with the `PlayerModel` id `id`, if there is one.
+pub fn model_by_id(id: u64) -> Option<Model> {
+    MODELS.into_iter().find(|model| u64::from(model.id) == id)
+}
+
+/// Returns the model for `gender` and `species`.
+pub fn model_for(gender: Gender, species: Species) -> Model {
+    MODELS
+        .into_iter()
+        .find(|model| model.gender == gender && model.species == species)
+        .expect("every gender and species combination has a model")
+}
+
+/// Equipment slot of a job profile, serialized in lowercase, e.g. `"shirt"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EquipmentSlot {
+    Head,
+    Gloves,
+    Shirt,
+    Pants,
+    Shoes,
+    Back,
+    Weapon,
+    Costume,
+}
+
+impl EquipmentSlot {
+    /// Returns the id the game uses for the slot, written to `Item1` and `Category`.
+    pub fn id(&self) -> u8 {
+        match self {
+            EquipmentSlot::Head => 1,
+            EquipmentSlot::Gloves => 2,
+            EquipmentSlot::Shirt => 3,
+            EquipmentSlot::Pants => 4,
+            EquipmentSlot::Shoes => 5,
+            EquipmentSlot::Back => 6,
+            EquipmentSlot::Weapon => 7,
+            EquipmentSlot::Costume => 17,
+        }
+    }
+}
+
+/// Accessory slot of a character, serialized in lowercase, e.g. `"glasses"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessorySlot {
+    Glasses,
+    Mask,
+    Earrings,
+}
+
+impl AccessorySlot {
+    /// Every accessory slot.
+    pub const ALL: [AccessorySlot; 3] = [AccessorySlot::Glasses, AccessorySlot::Mask, AccessorySlot::Earrings];
+
+    /// Returns the slot as stored in the database, e.g. `"glasses"`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccessorySlot::Glasses => "glasses",
+            AccessorySlot::Mask => "mask",
+            AccessorySlot::Earrings => "earrings",
+        }
+    }
+
+    /// Returns the field of a character file holding the accessory worn in the slot.
+    pub fn field(&self) -> &'static str {
+        match self {
+            AccessorySlot::Glasses => "Glasses",
+            AccessorySlot::Mask => "Mask",
+            AccessorySlot::Earrings => "Earrings",
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use rand::{seq::SliceRandom, Rng};
+use serde::Serialize;
+
+/// Name parts for humans, combined as start + end.
+const HUMAN_FIRST_STARTS: [&str; 16] = [
+    "Al", "Bel", "Bran", "Cor", "Dar", "Ed", "Fen", "Gar", "Hal", "Jor", "Kel", "Lor", "Mar", "Ros", "Tam", "Wil",
+];
+const HUMAN_MALE_ENDS: [&str; 10] = ["an", "ard", "as", "en", "ric", "ick", "on", "us", "win", "ald"];
+const HUMAN_FEMALE_ENDS: [&str; 10] = ["a", "ia", "ina", "elle", "wen", "ys", "ara", "eth", "ine", "issa"];
+
+/// Name parts for fairies, combined as start + end.
+const FAIRY_FIRST_STARTS: [&str; 16] = [
+    "Bri", "Dew", "Fae", "Fern", "Flit", "Glim", "Lil", "Lumi", "Moss", "Nim", "Pip", "Quill", "Sil", "Thist", "Twin", "Wisp",
+];
+const FAIRY_MALE_ENDS: [&str; 10] = ["bright", "kin", "lo", "wick", "ton", "by", "rin", "dle", "sprig", "thorn"];
+const FAIRY_FEMALE_ENDS: [&str; 10] = ["belle", "bloom", "ette", "lia", "wyn", "sy", "ra", "petal", "shine", "y"];
+
+const HUMAN_LAST_STARTS: [&str; 12] = ["Ash", "Black", "Brook", "Copper", "Gold", "Hawk", "Iron", "Oak", "Raven", "Stone", "Storm", "Thorn"];
+const HUMAN_LAST_ENDS: [&str; 10] = ["wood", "field", "smith", "hart", "ford", "well", "ridge", "vale", "brook", "shaw"];
+
+const FAIRY_LAST_STARTS: [&str; 12] = ["Bramble", "Dew", "Ember", "Fern", "Glimmer", "Honey", "Moon", "Petal", "Sparkle", "Star", "Thistle", "Willow"];
+const FAIRY_LAST_ENDS: [&str; 10] = ["wing", "leaf", "dust", "glow", "song", "drop", "bloom", "gleam", "whisper", "flutter"];
+
+/// A randomly generated character name.
+#[derive(Debug, Serialize)]
+pub struct GeneratedName {
+    first_name: String,
+    last_name: String,
+}
+
+/// Picks a random element of `parts`.
+fn pick<'a>(rng: &mut impl Rng, parts: &[&'a str]) -> &'a str {
+    parts.choose(rng).copied().unwrap_or_default()
+}
+
+/// Generates a random first and last name fitting `species` and `gender`, as used by the
+/// database ("human"/"fairy" and "m"/"f").
+///
+/// Names are built from embedded name parts, so every combination reads like a name from the
+/// realms. Unknown species fall back to human names.
+pub fn generate_name(species: &str, gender: &str) -> GeneratedName {
+    let mut rng = rand::thread_rng();
+
+    let (first_starts, male_ends, female_ends, last_starts, last_ends) = match species {
+        "fairy" => (&FAIRY_FIRST_STARTS, &FAIRY_MALE_ENDS, &FAIRY_FEMALE_ENDS, &FAIRY_LAST_STARTS, &FAIRY_LAST_ENDS),
+        _ => (&HUMAN_FIRST_STARTS, &HUMAN_MALE_ENDS, &HUMAN_FEMALE_ENDS, &HUMAN_LAST_STARTS, &HUMAN_LAST_ENDS),
+    };
+    let first_ends = match gender {
+        "f" => female_ends,
+        "m" => male_ends,
+        _ => if rng.gen_bool(0.5) { female_ends } else { male_ends },
+    };
+
+    GeneratedName {
+        first_name: format!("{}{}", pick(&mut rng, first_starts), pick(&mut rng, first_ends)),
+        last_name: format!("{}{}", pick(&mut rng, last_starts), pick(&mut rng, last_ends)),
+    }
+}
@@ -0,0 +1,101 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+/// Folder created inside the platform directories, named after the app identifier so it matches
+/// the folders Tauri resolves as the app config and data directories.
+const APP_DIR_NAME: &str = "com.character-creator.dev";
+
+/// File next to the executable recording the storage mode chosen by the user.
+const STORAGE_MODE_FILE: &str = "storage-mode";
+
+/// Storage mode read at startup. A new mode only applies after a restart, so the data of a
+/// running session never ends up split between two places.
+static STORAGE_MODE: Mutex<Option<StorageMode>> = Mutex::new(None);
+
+/// Where the app keeps the data the user creates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageMode {
+    /// Everything is kept next to the executable, e.g. to run the app from a USB drive. This is
+    /// how every earlier version stored its data, and stays the default.
+    Portable,
+    /// Data and settings are kept in the platform directories of the current user, e.g.
+    /// `%APPDATA%` on Windows, so the app can be installed in a read-only location.
+    Installed,
+}
+
+/// Returns the folder the app is run from, which holds the bundled resources and the data of
+/// portable installs.
+///
+/// Debug builds use the working directory, since the executable lives in `target/` while the
+/// resources stay in the crate folder.
+fn app_dir() -> PathBuf {
+    let exe_dir = std::env::current_exe().ok().and_then(|exe| exe.parent().map(Path::to_path_buf));
+    match (cfg!(debug_assertions), exe_dir) {
+        (false, Some(dir)) => dir,
+        _ => PathBuf::from("."),
+    }
+}
+
+/// Reads the storage mode file, defaulting to portable if it is missing or unreadable.
+fn read_storage_mode() -> StorageMode {
+    match fs::read_to_string(app_dir().join(STORAGE_MODE_FILE)).as_deref().map(str::trim) {
+        Ok("installed") => StorageMode::Installed,
+        Ok("portable") | Err(_) => StorageMode::Portable,
+        Ok(other) => {
+            warn!("Unknown storage mode {}, using portable", other);
+            StorageMode::Portable
+        },
+    }
+}
+
+/// Returns the storage mode of the current session.
+pub fn storage_mode() -> StorageMode {
+    *STORAGE_MODE.lock().unwrap().get_or_insert_with(read_storage_mode)
+}
+
+/// Records `mode` as the storage mode to use from the next start on.
+///
+/// Existing data is not moved, the user is expected to copy it over if needed.
+pub fn set_storage_mode(mode: StorageMode) -> Result<(), std::io::Error> {
+    let content = match mode {
+        StorageMode::Portable => "portable",
+        StorageMode::Installed => "installed",
+    };
+    fs::write(app_dir().join(STORAGE_MODE_FILE), content)?;
+    info!("Storage mode set to {} for the next start", content);
+    Ok(())
+}
+
+/// Returns the folder holding the data the user creates, such as characters and backups.
+pub fn data_dir() -> PathBuf {
+    match (storage_mode(), tauri::api::path::data_dir()) {
+        (StorageMode::Installed, Some(dir)) => dir.join(APP_DIR_NAME),
+        _ => app_dir(),
+    }
+}
+
+/// Returns the folder holding the settings file.
+pub fn config_dir() -> PathBuf {
+    match (storage_mode(), tauri::api::path::config_dir()) {
+        (StorageMode::Installed, Some(dir)) => dir.join(APP_DIR_NAME),
+        _ => app_dir(),
+    }
+}
+
+/// Resolves `relative` against the data directory. Absolute paths are returned unchanged.
+pub fn data_path(relative: &str) -> String {
+    data_dir().join(relative).to_string_lossy().into_owned()
+}
+
+/// Resolves `relative` against the folder of the resources bundled with the app, such as the
+/// database and the character template. Absolute paths are returned unchanged.
+pub fn resource_path(relative: &str) -> String {
+    app_dir().join(relative).to_string_lossy().into_owned()
+}
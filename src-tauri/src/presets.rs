@@ -0,0 +1,73 @@
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use log::{info, warn};
+use serde::Serialize;
+
+use crate::customization::AppearanceDelta;
+use crate::filename::sanitize_file_stem;
+use crate::paths::data_path;
+
+const PRESET_DIR: &str = "presets";
+
+/// Returns the presets directory, inside the data directory.
+fn preset_dir() -> String {
+    data_path(PRESET_DIR)
+}
+
+/// A named appearance stored by the user to be applied to other characters.
+#[derive(Debug, Serialize)]
+pub struct Preset {
+    name: String,
+    appearance: AppearanceDelta,
+}
+
+/// Returns the path of the preset file named `name`.
+fn preset_path(name: &str) -> Result<String, std::io::Error> {
+    Ok(format!("{}/{}.json", preset_dir(), sanitize_file_stem(name)?))
+}
+
+/// Saves `appearance` as the preset `name`, replacing any preset with the same name.
+pub fn save_preset(name: &str, appearance: &AppearanceDelta) -> Result<(), std::io::Error> {
+    let file_path = preset_path(name)?;
+    fs::create_dir_all(preset_dir())?;
+
+    info!("Saving Preset {}", name);
+    let mut writer = BufWriter::new(File::create(file_path)?);
+    serde_json::to_writer_pretty(&mut writer, appearance)?;
+    writer.flush()
+}
+
+/// Reads the preset `name`.
+pub fn load_preset(name: &str) -> Result<AppearanceDelta, std::io::Error> {
+    let file = File::open(preset_path(name)?)?;
+    Ok(serde_json::from_reader(file)?)
+}
+
+/// Returns every preset stored in the "presets" directory, sorted by name.
+///
+/// Presets that can't be parsed are logged and skipped.
+pub fn list_presets() -> Result<Vec<Preset>, std::io::Error> {
+    if !Path::new(&preset_dir()).exists() {
+        return Ok(vec![]);
+    }
+
+    let mut presets: Vec<Preset> = vec![];
+    for entry in fs::read_dir(preset_dir())? {
+        let path = entry?.path();
+        if path.extension().map_or(true, |extension| extension != "json") {
+            continue;
+        }
+        let name = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+
+        match load_preset(&name) {
+            Ok(appearance) => presets.push(Preset { name, appearance }),
+            Err(e) => warn!("Skipping Preset {} due to {:#?}", name, e),
+        }
+    }
+    presets.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(presets)
+}
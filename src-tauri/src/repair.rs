@@ -0,0 +1,51 @@
+use serde_json::{Deserializer, Map, Value};
+
+/// Parses a single JSON value at the start of `input`.
+///
+/// # Returns
+///
+/// The value and the amount of bytes it took, or `None` if no complete value could be read.
+fn parse_value<T: serde::de::DeserializeOwned>(input: &str) -> Option<(T, usize)> {
+    let mut stream = Deserializer::from_str(input).into_iter::<T>();
+    let value = stream.next()?.ok()?;
+    Some((value, stream.byte_offset()))
+}
+
+/// Recovers every top-level field that can still be read from the broken character file
+/// `content`.
+///
+/// Fields are read in order until the first one that can't be parsed, which covers the usual
+/// corruption of a file cut short by a crash or a full disk. Everything from that point on is
+/// lost and has to be filled in by the caller.
+pub fn salvage_fields(content: &str) -> Map<String, Value> {
+    let mut fields = Map::new();
+    let mut rest = match content.trim_start().strip_prefix('{') {
+        Some(rest) => rest,
+        None => return fields,
+    };
+
+    loop {
+        rest = rest.trim_start().trim_start_matches(',').trim_start();
+        let (key, used) = match parse_value::<String>(rest) {
+            Some(key) => key,
+            None => break,
+        };
+        rest = rest[used..].trim_start();
+        rest = match rest.strip_prefix(':') {
+            Some(rest) => rest,
+            None => break,
+        };
+        let (value, used) = match parse_value::<Value>(rest) {
+            Some(value) => value,
+            None => break,
+        };
+        rest = rest[used..].trim_start();
+        // A value running into the end of the file may have been cut, e.g. a number losing its
+        // last digits, so only values followed by another token are trusted.
+        if rest.is_empty() {
+            break;
+        }
+        fields.insert(key, value);
+    }
+    fields
+}
@@ -0,0 +1,318 @@
+use std::path::Path;
+
+use log::{info, warn};
+use rusqlite::{Connection, OpenFlags, OptionalExtension, TransactionBehavior};
+use serde::Serialize;
+
+use crate::database::has_table;
+use crate::error::DatabaseError;
+use crate::settings::database_path;
+
+/// Version of the cosmetics database schema expected by this build. Databases without a
+/// `schema_version` table predate versioning and are treated as version 0.
+pub const CURRENT_DATABASE_VERSION: u64 = 1;
+
+/// Scripts upgrading the cosmetics database, as the version they upgrade to and the SQL to run.
+/// Databases older than that version get the script run once, in order, so a new column or table
+/// reaches every user without them editing their database.
+///
+/// Scripts must work on any database of the previous version, even one edited by hand, e.g. by
+/// creating tables only if they don't exist yet.
+const MIGRATIONS: &[(u64, &str)] = &[(
+    1,
+    "CREATE TABLE IF NOT EXISTS Cosmetic_Tag (
+        category TEXT NOT NULL,
+        value TEXT NOT NULL,
+        tag TEXT NOT NULL,
+        PRIMARY KEY (category, value, tag)
+    );
+    CREATE TABLE IF NOT EXISTS Constraints (
+        id INTEGER PRIMARY KEY,
+        field TEXT NOT NULL,
+        value TEXT NOT NULL,
+        other_field TEXT NOT NULL,
+        other_value TEXT NOT NULL,
+        reason TEXT
+    );
+    CREATE TABLE IF NOT EXISTS Palette (
+        kind TEXT NOT NULL,
+        color INTEGER NOT NULL,
+        rgb TEXT NOT NULL,
+        PRIMARY KEY (kind, color)
+    );",
+)];
+
+/// Returns the version of the schema of the database `conn`, 0 if it isn't versioned.
+fn database_version(conn: &Connection) -> Result<u64, DatabaseError> {
+    let versioned: bool = conn
+        .query_row("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'schema_version'", [], |_| Ok(true))
+        .optional()?
+        .unwrap_or(false);
+    if !versioned {
+        return Ok(0);
+    }
+    let version: Option<u64> = conn.query_row("SELECT MAX(version) FROM schema_version", [], |row| row.get(0))?;
+    Ok(version.unwrap_or(0))
+}
+
+/// Runs the `MIGRATIONS` the database `conn` hasn't had yet, recording each in its
+/// `schema_version` table.
+///
+/// Migrations run in a single transaction taking the write lock first, so connections opened
+/// at the same time don't run them twice, and a failing script leaves the database untouched.
+pub fn migrate(conn: &mut Connection) -> Result<(), DatabaseError> {
+    if database_version(conn)? >= CURRENT_DATABASE_VERSION {
+        return Ok(());
+    }
+
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            applied_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );",
+    )?;
+    let version = database_version(&tx)?;
+    for (target, script) in MIGRATIONS.iter().filter(|(target, _)| *target > version) {
+        info!("Migrating the Database file to version {}", target);
+        tx.execute_batch(script)?;
+        tx.execute("INSERT INTO schema_version (version) VALUES (?)", [target])?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// A table the queries read, with the columns they need as their name and declared type.
+struct TableSchema {
+    name: &'static str,
+    columns: &'static [(&'static str, &'static str)],
+    /// Whether queries fail without the table. Optional tables are only checked if present.
+    required: bool,
+}
+
+const NAME_COLOR: &[(&str, &str)] = &[("name", "TEXT"), ("color", "INTEGER")];
+
+/// Tables and columns of the cosmetics database read by the queries of `database`. Optional
+/// columns, e.g. `display_order` or `enabled`, are left out as the queries check for them.
+const EXPECTED_SCHEMA: &[TableSchema] = &[
+    TableSchema { name: "Hair", columns: &[("id", "INTEGER"), ("addr", "TEXT"), ("gender", "TEXT"), ("name", "TEXT")], required: true },
+    TableSchema { name: "FacePaint", columns: &[("id", "INTEGER"), ("texture_alias", "TEXT")], required: true },
+    TableSchema { name: "extras", columns: &[("id", "INTEGER"), ("name", "TEXT"), ("species", "TEXT"), ("gender", "TEXT"), ("addr", "TEXT")], required: true },
+    TableSchema { name: "Eye_Color", columns: NAME_COLOR, required: true },
+    TableSchema { name: "Hair_Color", columns: NAME_COLOR, required: true },
+    TableSchema { name: "Hair_Highlight_Color", columns: NAME_COLOR, required: true },
+    TableSchema { name: "Wing_Color", columns: NAME_COLOR, required: true },
+    TableSchema { name: "FacePaint_Color", columns: NAME_COLOR, required: true },
+    TableSchema { name: "Species", columns: &[("model", "INTEGER"), ("species", "TEXT"), ("gender", "TEXT"), ("name", "TEXT")], required: true },
+    TableSchema { name: "Eyebrows", columns: &[("id", "INTEGER"), ("name", "TEXT"), ("gender", "TEXT"), ("addr", "TEXT")], required: true },
+    TableSchema { name: "FaceShapes", columns: &[("id", "INTEGER"), ("name", "TEXT"), ("species", "TEXT"), ("gender", "TEXT"), ("addr", "TEXT")], required: true },
+    TableSchema {
+        name: "BodyTypes",
+        columns: &[("species", "TEXT"), ("min_body_type", "INTEGER"), ("max_body_type", "INTEGER"), ("min_scale", "REAL"), ("max_scale", "REAL")],
+        required: true,
+    },
+    TableSchema { name: "Body_Type", columns: &[("id", "INTEGER"), ("name", "TEXT"), ("species", "TEXT"), ("gender", "TEXT")], required: true },
+    TableSchema { name: "Voices", columns: &[("id", "INTEGER"), ("name", "TEXT"), ("species", "TEXT"), ("gender", "TEXT")], required: true },
+    TableSchema { name: "Titles", columns: &[("id", "INTEGER"), ("place", "INTEGER"), ("title_name", "INTEGER")], required: true },
+    TableSchema { name: "Classes", columns: &[("id", "INTEGER"), ("name", "TEXT"), ("job_name", "INTEGER")], required: true },
+    TableSchema { name: "Equipment", columns: &[("guid", "INTEGER"), ("name", "TEXT"), ("slot", "INTEGER")], required: true },
+    TableSchema { name: "Tails", columns: &[("id", "INTEGER"), ("name", "TEXT"), ("species", "TEXT"), ("addr", "TEXT")], required: true },
+    TableSchema { name: "Ears", columns: &[("id", "INTEGER"), ("name", "TEXT"), ("species", "TEXT"), ("gender", "TEXT"), ("addr", "TEXT")], required: true },
+    TableSchema { name: "Markings", columns: &[("id", "INTEGER"), ("name", "TEXT"), ("species", "TEXT"), ("gender", "TEXT"), ("addr", "TEXT")], required: true },
+    TableSchema { name: "Skintone", columns: &[("id", "INTEGER"), ("name", "TEXT"), ("species", "TEXT"), ("addr", "TEXT")], required: true },
+    TableSchema { name: "Eye_Shape", columns: &[("id", "INTEGER"), ("name", "TEXT"), ("species", "TEXT"), ("addr", "TEXT")], required: true },
+    TableSchema {
+        name: "Accessories",
+        columns: &[("id", "INTEGER"), ("name", "TEXT"), ("slot", "TEXT"), ("species", "TEXT"), ("gender", "TEXT"), ("addr", "TEXT")],
+        required: true,
+    },
+    TableSchema { name: "Cosmetic_Tag", columns: &[("category", "TEXT"), ("value", "TEXT"), ("tag", "TEXT")], required: false },
+    TableSchema {
+        name: "Constraints",
+        columns: &[("id", "INTEGER"), ("field", "TEXT"), ("value", "TEXT"), ("other_field", "TEXT"), ("other_value", "TEXT"), ("reason", "TEXT")],
+        required: false,
+    },
+    TableSchema { name: "Palette", columns: &[("kind", "TEXT"), ("color", "INTEGER"), ("rgb", "TEXT")], required: false },
+];
+
+/// Rows seeding the tables `create_missing_tables` creates, as the table and the `INSERT`
+/// statement, so a table missing from an old data pack still offers its usual choices. Every
+/// table must be in `EXPECTED_SCHEMA`.
+const DEFAULT_ROWS: &[(&str, &str)] = &[
+    (
+        "Species",
+        "INSERT INTO Species (model, species, gender, name) VALUES
+            (1, 'human', 'm', 'Male Human'),
+            (2, 'fairy', 'm', 'Male Pixie'),
+            (60, 'human', 'f', 'Female Human'),
+            (61, 'fairy', 'f', 'Female Pixie');",
+    ),
+    (
+        "FaceShapes",
+        "INSERT INTO FaceShapes (id, name, species, gender, addr) VALUES
+            (1, 'caucasian', 'human', 'f', '<race>_<gender>_head_caucasian.adr'),
+            (2, 'caucasian', 'human', 'm', '<race>_<gender>_head_caucasian.adr'),
+            (3, 'caucasian', 'fairy', 'f', '<race>_<gender>_head_caucasian.adr'),
+            (4, 'caucasian', 'fairy', 'm', '<race>_<gender>_head_caucasian.adr');",
+    ),
+    (
+        "Skintone",
+        "INSERT INTO Skintone (id, name, species, addr) VALUES
+            (1, 'Fair', 'human', 'skintone1'),
+            (2, 'Light', 'human', 'skintone2'),
+            (3, 'Gold', 'human', 'skintone3'),
+            (4, 'Tanned', 'human', 'skintone4'),
+            (5, 'Dark', 'human', 'skintone5'),
+            (6, 'Ebony', 'human', 'skintone6'),
+            (7, 'Fair', 'fairy', 'skintone1'),
+            (8, 'Light', 'fairy', 'skintone2'),
+            (9, 'Gold', 'fairy', 'skintone3'),
+            (10, 'Tanned', 'fairy', 'skintone4'),
+            (11, 'Dark', 'fairy', 'skintone5'),
+            (12, 'Ebony', 'fairy', 'skintone6');",
+    ),
+];
+
+/// Creates the tables of `DEFAULT_ROWS` the database `conn` lacks, seeded with their default
+/// rows, so users of an old data pack get defaults instead of failing queries.
+///
+/// Only tables with defaults are created, the other missing tables are left for `check_schema`
+/// to report, as an empty table would hide a wrong or outdated database behind empty lists.
+///
+/// The tables are created in a single transaction taking the write lock first, so connections
+/// opened at the same time don't create them twice.
+pub fn create_missing_tables(conn: &mut Connection) -> Result<(), DatabaseError> {
+    let mut missing: Vec<(&TableSchema, &str)> = vec![];
+    for (name, rows) in DEFAULT_ROWS {
+        let table = EXPECTED_SCHEMA.iter().find(|table| table.name == *name)
+            .expect("every table with default rows is in the expected schema");
+        if !has_table(conn, table.name)? {
+            missing.push((table, rows));
+        }
+    }
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    for (table, rows) in missing {
+        if has_table(&tx, table.name)? {
+            continue;
+        }
+        warn!("Table {} is missing from the Database file, creating it with its default rows", table.name);
+        let columns: Vec<String> = table.columns.iter().map(|(name, kind)| format!("{} {}", name, kind)).collect();
+        tx.execute_batch(&format!("CREATE TABLE {} ({});", table.name, columns.join(", ")))?;
+        tx.execute_batch(rows)?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Kind of problem found in the schema of the cosmetics database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SchemaIssueKind {
+    /// A table the queries read doesn't exist.
+    MissingTable,
+    /// A column the queries read doesn't exist.
+    MissingColumn,
+    /// A column is declared with a type of another affinity than the queries expect, e.g. a
+    /// color declared as `TEXT`.
+    WrongType,
+}
+
+/// A problem found in the schema of the cosmetics database, reported at startup instead of
+/// failing the first query reading the table.
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemaIssue {
+    pub table: String,
+    /// The column, `None` for problems of the whole table.
+    pub column: Option<String>,
+    pub kind: SchemaIssueKind,
+    pub message: String,
+}
+
+/// Returns the type affinity SQLite gives a column declared as `declared`, following
+/// <https://www.sqlite.org/datatype3.html#determination_of_column_affinity>.
+fn affinity(declared: &str) -> &'static str {
+    let declared = declared.to_uppercase();
+    if declared.contains("INT") {
+        "INTEGER"
+    } else if declared.contains("CHAR") || declared.contains("CLOB") || declared.contains("TEXT") {
+        "TEXT"
+    } else if declared.is_empty() || declared.contains("BLOB") {
+        "BLOB"
+    } else if declared.contains("REAL") || declared.contains("FLOA") || declared.contains("DOUB") {
+        "REAL"
+    } else {
+        "NUMERIC"
+    }
+}
+
+/// Returns the columns of `table` with their declared type, none if there is no such table.
+fn columns_of(conn: &Connection, table: &str) -> Result<Vec<(String, String)>, DatabaseError> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let column_iter = stmt.query_map([], |row| Ok((row.get::<_, String>(1)?, row.get::<_, String>(2)?)))?;
+    let mut columns: Vec<(String, String)> = vec![];
+    for column in column_iter {
+        columns.push(column?);
+    }
+    Ok(columns)
+}
+
+/// Checks that the cosmetics database of the settings has every table and column the queries
+/// read, with the expected types.
+///
+/// The file is opened read-only instead of through the pool of `Database`, as opening it there
+/// creates the tables having default rows, so tables missing from the file are still reported.
+/// Columns declared without a type are accepted, SQLite stores any value in them.
+///
+/// # Returns
+///
+/// Every problem found, empty when the schema is as expected, or a `DatabaseError` if the
+/// database could not be opened.
+pub fn check_schema() -> Result<Vec<SchemaIssue>, DatabaseError> {
+    let path = database_path();
+    if !Path::new(&path).is_file() {
+        return Err(DatabaseError::Missing { path });
+    }
+    let conn = Connection::open_with_flags(&path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    let mut issues: Vec<SchemaIssue> = vec![];
+
+    for table in EXPECTED_SCHEMA {
+        let columns = columns_of(&conn, table.name)?;
+        if columns.is_empty() {
+            if table.required {
+                issues.push(SchemaIssue {
+                    table: table.name.to_string(),
+                    column: None,
+                    kind: SchemaIssueKind::MissingTable,
+                    message: format!("Table {} is missing", table.name),
+                });
+            }
+            continue;
+        }
+
+        for (column, expected) in table.columns {
+            let (kind, message) = match columns.iter().find(|(name, _)| name.eq_ignore_ascii_case(column)) {
+                None => (SchemaIssueKind::MissingColumn, format!("Column {} of table {} is missing", column, table.name)),
+                Some((_, declared)) if !["BLOB", *expected].contains(&affinity(declared)) => (
+                    SchemaIssueKind::WrongType,
+                    format!("Column {} of table {} is declared as {} instead of {}", column, table.name, declared, expected),
+                ),
+                Some(_) => continue,
+            };
+            issues.push(SchemaIssue { table: table.name.to_string(), column: Some(column.to_string()), kind, message });
+        }
+    }
+
+    match issues.is_empty() {
+        true => info!("The schema of the Database file is as expected"),
+        false => {
+            for issue in &issues {
+                warn!("{}", issue.message);
+            }
+        },
+    }
+    Ok(issues)
+}
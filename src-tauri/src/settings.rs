@@ -0,0 +1,242 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::paths::{config_dir, data_path, resource_path};
+
+const SETTINGS_FILE_NAME: &str = "settings.toml";
+
+/// Directory the characters are kept in when the user didn't choose one.
+pub const DEFAULT_CHARACTERS_DIR: &str = "characters";
+pub const DEFAULT_DATABASE_PATH: &str = "database/customization.db";
+/// Directory the templates are kept in when the active profile didn't choose one.
+pub const DEFAULT_TEMPLATES_DIR: &str = "templates";
+
+/// Settings loaded from the settings file, read once and kept for the rest of the session.
+static SETTINGS: Mutex<Option<Settings>> = Mutex::new(None);
+
+/// User configuration persisted between sessions.
+///
+/// Every field has a default, so settings files written by older versions keep loading.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub database_path: String,
+    pub characters_dir: Option<String>,
+    pub backup: BackupSettings,
+    pub language: String,
+    /// Write character files indented, one key per line, so they are easy to edit by hand and to
+    /// diff, e.g. when kept in Git.
+    pub pretty_json: bool,
+    /// Most characters the characters directory may hold, matching the slots of the server, or
+    /// `None` for no limit.
+    pub max_characters: Option<usize>,
+    pub profiles: Vec<Profile>,
+    /// Name of the profile in use, or `None` to use the directories of these settings.
+    pub active_profile: Option<String>,
+    /// URL of the manifest describing the latest cosmetics database, or `None` for the one
+    /// attached to the latest release of the creator.
+    pub database_update_url: Option<String>,
+}
+
+/// A named set of directories for one OSFR server, so the characters of players on several
+/// servers are kept apart.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    /// Defaults to a directory named after the profile inside the default characters directory.
+    pub characters_dir: Option<String>,
+    /// Defaults to the default templates directory, shared with the other profiles.
+    pub templates_dir: Option<String>,
+    /// Defaults to the database of these settings.
+    pub database_path: Option<String>,
+}
+
+impl Settings {
+    /// Returns the profile in use, if any.
+    fn active_profile(&self) -> Option<&Profile> {
+        let name = self.active_profile.as_deref()?;
+        self.profiles.iter().find(|profile| profile.name == name)
+    }
+
+    /// Returns the characters directory of the active profile, or of these settings, unresolved.
+    fn characters_dir(&self) -> String {
+        match self.active_profile() {
+            Some(profile) => profile.characters_dir.clone()
+                .unwrap_or_else(|| format!("{}/{}", DEFAULT_CHARACTERS_DIR, profile.name)),
+            None => self.characters_dir.clone().unwrap_or_else(|| DEFAULT_CHARACTERS_DIR.to_string()),
+        }
+    }
+
+    /// Returns the templates directory of the active profile, unresolved.
+    fn templates_dir(&self) -> &str {
+        self.active_profile()
+            .and_then(|profile| profile.templates_dir.as_deref())
+            .unwrap_or(DEFAULT_TEMPLATES_DIR)
+    }
+
+    /// Returns the database of the active profile, or of these settings, unresolved.
+    fn database_path(&self) -> &str {
+        self.active_profile()
+            .and_then(|profile| profile.database_path.as_deref())
+            .unwrap_or(&self.database_path)
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            database_path: DEFAULT_DATABASE_PATH.to_string(),
+            characters_dir: None,
+            backup: BackupSettings::default(),
+            language: "en".to_string(),
+            pretty_json: false,
+            max_characters: None,
+            profiles: vec![],
+            active_profile: None,
+            database_update_url: None,
+        }
+    }
+}
+
+/// Controls the backups taken before a character file is overwritten.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupSettings {
+    pub enabled: bool,
+    /// Amount of backups kept per character, older ones are removed after every new backup.
+    pub max_backups: usize,
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        BackupSettings { enabled: true, max_backups: 10 }
+    }
+}
+
+/// Returns the path of the settings file, next to the executable in portable mode and in the
+/// platform config directory otherwise, e.g.
+/// `%APPDATA%\com.character-creator.dev\settings.toml` on Windows.
+fn settings_path() -> PathBuf {
+    config_dir().join(SETTINGS_FILE_NAME)
+}
+
+/// Reads the settings file, falling back to the defaults if it is missing or unreadable.
+fn read_settings() -> Settings {
+    let path = settings_path();
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(_) => return Settings::default(),
+    };
+    match toml::from_str(&content) {
+        Ok(settings) => settings,
+        Err(e) => {
+            warn!("Ignoring unreadable settings file {} due to {:#?}", path.display(), e);
+            Settings::default()
+        },
+    }
+}
+
+/// Returns the current settings.
+pub fn get_settings() -> Settings {
+    let mut settings = SETTINGS.lock().unwrap();
+    settings.get_or_insert_with(read_settings).clone()
+}
+
+/// Persists `settings` and makes them the current settings.
+///
+/// The file is written through a temporary file, so a failed write leaves the previous settings
+/// in place.
+fn save_settings(settings: &Settings) -> Result<(), std::io::Error> {
+    let content = toml::to_string_pretty(settings)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+    let path = settings_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let temp_path = path.with_extension("toml.tmp");
+    fs::write(&temp_path, content)?;
+    fs::rename(&temp_path, &path)?;
+
+    *SETTINGS.lock().unwrap() = Some(settings.clone());
+    info!("Saved settings {:?}", settings);
+    Ok(())
+}
+
+/// Checks `settings` and, if they are usable, saves them as the new settings.
+///
+/// The characters directory is created if needed and the database must exist, so a path that
+/// can't be used fails here instead of on the next save. Relative paths are resolved the same
+/// way `characters_dir` and `database_path` do. The active profile, if any, must be one of the
+/// profiles.
+pub fn update_settings(settings: &Settings) -> Result<(), std::io::Error> {
+    if let Some(name) = &settings.active_profile {
+        if settings.active_profile().is_none() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("There is no profile named {}", name),
+            ));
+        }
+    }
+    fs::create_dir_all(data_path(&settings.characters_dir()))?;
+    if !Path::new(&resource_path(settings.database_path())).is_file() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("Database file {} does not exist", settings.database_path()),
+        ));
+    }
+    save_settings(settings)
+}
+
+/// Returns the path of the cosmetics database of the active profile, resolved against the
+/// bundled resources.
+pub fn database_path() -> String {
+    resource_path(get_settings().database_path())
+}
+
+/// Returns the directory the character files of the active profile are read from and written
+/// to, resolved against the data directory.
+pub fn characters_dir() -> String {
+    data_path(&get_settings().characters_dir())
+}
+
+/// Returns the directory the templates of the active profile are kept in, resolved against the
+/// data directory.
+pub fn templates_dir() -> String {
+    data_path(get_settings().templates_dir())
+}
+
+/// Makes `dir` the characters directory of the active profile, or restores the default one if
+/// `dir` is `None`.
+///
+/// The directory is created if needed, so a path that can't be used fails here instead of on
+/// the next save.
+pub fn set_characters_dir(dir: Option<&str>) -> Result<(), std::io::Error> {
+    let mut settings = get_settings();
+    let active_profile = settings.active_profile.clone();
+    match settings.profiles.iter_mut().find(|profile| Some(&profile.name) == active_profile.as_ref()) {
+        Some(profile) => profile.characters_dir = dir.map(str::to_string),
+        None => settings.characters_dir = dir.map(str::to_string),
+    }
+    update_settings(&settings)
+}
+
+/// Returns every profile of the settings.
+pub fn list_profiles() -> Vec<Profile> {
+    get_settings().profiles
+}
+
+/// Makes the profile `name` the one in use, or goes back to the directories of the settings if
+/// `name` is `None`. Fails with `std::io::ErrorKind::NotFound` if there is no such profile.
+pub fn switch_profile(name: Option<&str>) -> Result<(), std::io::Error> {
+    info!("Switching to profile {:?}", name);
+    let mut settings = get_settings();
+    settings.active_profile = name.map(str::to_string);
+    update_settings(&settings)
+}
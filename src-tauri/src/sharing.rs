@@ -0,0 +1,142 @@
+use base64::{engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD}, Engine};
+use qrcode::{Color, QrCode};
+use serde_json::{json, Value};
+
+use crate::customization::AppearanceDelta;
+
+/// Prefix of every appearance code, bumped whenever the encoded layout changes.
+const CODE_PREFIX: &str = "FR2:";
+
+/// Prefix of the codes of the first layout, holding only the 7 fields the creator started with.
+/// They are still decoded, leaving the other fields unchanged.
+const LEGACY_CODE_PREFIX: &str = "FR1:";
+
+/// Amount of fields in a code of each layout, see `encode_appearance`.
+const LEGACY_FIELD_COUNT: usize = 7;
+const FIELD_COUNT: usize = 26;
+
+/// Size in pixels of a single QR code module.
+const QR_MODULE_SIZE: usize = 8;
+
+/// Width in modules of the blank border around a QR code, required by scanners.
+const QR_QUIET_ZONE: usize = 4;
+
+/// Encodes `appearance` into a short code players can paste to each other.
+///
+/// The appearance fields are stored positionally in a JSON array, so the code doesn't carry any
+/// field names, and the array is encoded as URL-safe base64. The fields of the first layout come
+/// first, in the same order, and new fields are only ever appended.
+pub fn encode_appearance(appearance: &AppearanceDelta) -> String {
+    let fields = json!([
+        appearance.gender_race,
+        appearance.hair_type,
+        appearance.hair_color,
+        appearance.eye_color,
+        appearance.skintone,
+        appearance.facepaint,
+        appearance.extras,
+        appearance.face_shape,
+        appearance.hair_highlight_color,
+        appearance.eye_shape,
+        appearance.facepaint_color,
+        appearance.facepaint_opacity,
+        appearance.facial_hair,
+        appearance.wings,
+        appearance.wing_color,
+        appearance.eyebrow_style,
+        appearance.eyebrow_color,
+        appearance.body_type,
+        appearance.body_scale,
+        appearance.voice,
+        appearance.tail,
+        appearance.ears,
+        appearance.markings,
+        appearance.glasses,
+        appearance.mask,
+        appearance.earrings,
+    ]);
+    format!("{}{}", CODE_PREFIX, URL_SAFE_NO_PAD.encode(fields.to_string()))
+}
+
+/// Decodes an appearance code produced by `encode_appearance`, or by a version of the creator
+/// still using the first layout.
+///
+/// Fails with `std::io::ErrorKind::InvalidData` if `code` isn't a valid appearance code.
+pub fn decode_appearance(code: &str) -> Result<AppearanceDelta, std::io::Error> {
+    let invalid = |reason: String| std::io::Error::new(std::io::ErrorKind::InvalidData, reason);
+
+    let code = code.trim();
+    let (encoded, expected) = match (code.strip_prefix(CODE_PREFIX), code.strip_prefix(LEGACY_CODE_PREFIX)) {
+        (Some(encoded), _) => (encoded, FIELD_COUNT),
+        (_, Some(encoded)) => (encoded, LEGACY_FIELD_COUNT),
+        _ => return Err(invalid(format!("Appearance codes start with {}", CODE_PREFIX))),
+    };
+    let bytes = URL_SAFE_NO_PAD.decode(encoded).map_err(|e| invalid(e.to_string()))?;
+    let mut fields: Vec<Value> = serde_json::from_slice(&bytes)?;
+    if fields.len() != expected {
+        return Err(invalid(format!("Expected {} appearance fields, found {}", expected, fields.len())));
+    }
+    fields.resize(FIELD_COUNT, Value::Null);
+
+    let string = |index: usize| fields[index].as_str().map(str::to_string);
+    let number = |index: usize| fields[index].as_u64();
+    Ok(AppearanceDelta {
+        gender_race: number(0).map(|model| model as u8),
+        hair_type: string(1),
+        hair_color: number(2).map(|color| color as usize),
+        eye_color: number(3).map(|color| color as usize),
+        skintone: string(4),
+        facepaint: string(5),
+        extras: string(6),
+        face_shape: string(7),
+        hair_highlight_color: number(8).map(|color| color as usize),
+        eye_shape: string(9),
+        facepaint_color: number(10).map(|color| color as u32),
+        facepaint_opacity: fields[11].as_f64(),
+        facial_hair: string(12),
+        wings: string(13),
+        wing_color: number(14).map(|color| color as u32),
+        eyebrow_style: string(15),
+        eyebrow_color: number(16).map(|color| color as usize),
+        body_type: number(17).map(|body_type| body_type as u8),
+        body_scale: fields[18].as_f64(),
+        voice: number(19).map(|voice| voice as usize),
+        tail: string(20),
+        ears: string(21),
+        markings: fields[22].as_array()
+            .map(|markings| markings.iter().filter_map(Value::as_u64).map(|marking| marking as usize).collect()),
+        glasses: string(23),
+        mask: string(24),
+        earrings: string(25),
+    })
+}
+
+/// Renders `code` into a QR code and returns it as a PNG data URI, ready to be used as the
+/// source of an `<img>` or saved as an image.
+pub fn appearance_qr_code(code: &str) -> Result<String, std::io::Error> {
+    let qr = QrCode::new(code.as_bytes())
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e.to_string()))?;
+    let colors = qr.to_colors();
+    let width = qr.width();
+
+    let size = (width + 2 * QR_QUIET_ZONE) * QR_MODULE_SIZE;
+    let mut pixels = vec![255u8; size * size];
+    for (index, color) in colors.iter().enumerate() {
+        if *color != Color::Dark {
+            continue;
+        }
+        let x = (index % width + QR_QUIET_ZONE) * QR_MODULE_SIZE;
+        let y = (index / width + QR_QUIET_ZONE) * QR_MODULE_SIZE;
+        for row in y..y + QR_MODULE_SIZE {
+            pixels[row * size + x..row * size + x + QR_MODULE_SIZE].fill(0);
+        }
+    }
+
+    let mut image: Vec<u8> = vec![];
+    let mut encoder = png::Encoder::new(&mut image, size as u32, size as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&pixels)?;
+
+    Ok(format!("data:image/png;base64,{}", STANDARD.encode(image)))
+}
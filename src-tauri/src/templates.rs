@@ -0,0 +1,144 @@
+use std::{
+    fs,
+    io::ErrorKind,
+    path::Path,
+};
+
+use log::{info, warn};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::customization::{gender_species_of, ACCOUNT_FIELD, CREATED_AT_FIELD, FAVORITE_FIELD, ID_FIELD, NOTES_FIELD, UPDATED_AT_FIELD};
+use crate::filename::sanitize_file_stem;
+use crate::index::character_file;
+use crate::paths::resource_path;
+use crate::settings::{get_settings, templates_dir};
+use crate::utils::write_json_atomically;
+
+const FALLBACK_TEMPLATE_PATH: &str = "Fallback.json";
+
+/// A template available in the "templates" directory.
+#[derive(Debug, Serialize)]
+pub struct Template {
+    name: String,
+    gender: String,
+    species: String,
+}
+
+/// Copy of `Fallback.json` built into the binary, used when the external file is missing.
+const EMBEDDED_FALLBACK_TEMPLATE: &str = include_str!("../Fallback.json");
+
+/// Reads the template every new character starts from.
+///
+/// The external `Fallback.json` is used when present so it can be customized. If it is missing,
+/// the copy embedded in the binary is used instead and a warning is logged.
+pub fn load_fallback_template() -> Result<Value, std::io::Error> {
+    let content = match fs::read_to_string(resource_path(FALLBACK_TEMPLATE_PATH)) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            warn!("{} is missing, using the embedded template", FALLBACK_TEMPLATE_PATH);
+            EMBEDDED_FALLBACK_TEMPLATE.to_string()
+        },
+        Err(e) => return Err(e),
+    };
+    Ok(serde_json::from_str(&content)?)
+}
+
+/// Overwrites the external `Fallback.json` with the template embedded in the binary, undoing any
+/// customization or damage done to it.
+pub fn restore_fallback_template() -> Result<(), std::io::Error> {
+    info!("Restoring {} from the embedded template", FALLBACK_TEMPLATE_PATH);
+    fs::write(resource_path(FALLBACK_TEMPLATE_PATH), EMBEDDED_FALLBACK_TEMPLATE)
+}
+
+/// Returns the path of the template file named `name`, e.g. `templates/Human_Male.json`.
+fn template_path(name: &str) -> Result<String, std::io::Error> {
+    Ok(format!("{}/{}.json", templates_dir(), sanitize_file_stem(name)?))
+}
+
+/// Reads the template named `name` from the "templates" directory, or the fallback template if
+/// `name` is `None`.
+///
+/// Templates are complete character files, so species and gender specific defaults can be
+/// prepared once, e.g. as `Human_Male.json` or `Pixie_Female.json`.
+pub fn load_template(name: Option<&str>) -> Result<Value, std::io::Error> {
+    let name = match name {
+        Some(name) => name,
+        None => return load_fallback_template(),
+    };
+
+    let content = fs::read_to_string(template_path(name)?).map_err(|e| match e.kind() {
+        ErrorKind::NotFound => std::io::Error::new(ErrorKind::NotFound, format!("There is no template named {}", name)),
+        _ => e,
+    })?;
+    let template: Value = serde_json::from_str(&content)?;
+    if !template.is_object() {
+        return Err(std::io::Error::new(
+            ErrorKind::InvalidData,
+            format!("Template {} is not a JSON object", name),
+        ));
+    }
+    Ok(template)
+}
+
+/// Returns every template of the "templates" directory, sorted by name.
+///
+/// Templates that can't be read are logged and skipped.
+pub fn list_templates() -> Result<Vec<Template>, std::io::Error> {
+    let dir = templates_dir();
+    if !Path::new(&dir).exists() {
+        return Ok(vec![]);
+    }
+
+    let mut templates: Vec<Template> = vec![];
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if path.extension().map_or(true, |extension| extension != "json") {
+            continue;
+        }
+        let name = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+
+        match load_template(Some(&name)) {
+            Ok(template) => {
+                let (gender, species) = gender_species_of(template["PlayerModel"].as_u64().unwrap_or_default());
+                templates.push(Template { name, gender: gender.to_string(), species: species.to_string() });
+            },
+            Err(e) => warn!("Skipping template {} due to {:#?}", name, e),
+        }
+    }
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(templates)
+}
+
+/// Saves the character `id` as the template `name`, so future characters can start from it.
+///
+/// The id, name, account, notes, timestamps and favorite mark of the character are cleared, they
+/// are given to every character created from the template. Fails with `std::io::ErrorKind::AlreadyExists` if the template already exists.
+pub fn create_template_from_character(id: &str, name: &str) -> Result<(), std::io::Error> {
+    let file_path = template_path(name)?;
+    if Path::new(&file_path).exists() {
+        return Err(std::io::Error::new(
+            ErrorKind::AlreadyExists,
+            format!("Template {} already exists", name),
+        ));
+    }
+
+    let mut template: Value = serde_json::from_str(&fs::read_to_string(character_file(id)?)?)?;
+    if let Some(template) = template.as_object_mut() {
+        template.retain(|key, _| {
+            ![ID_FIELD, ACCOUNT_FIELD, NOTES_FIELD, FAVORITE_FIELD, CREATED_AT_FIELD, UPDATED_AT_FIELD].contains(&key.as_str())
+        });
+    }
+    template["FirstName"] = Value::String(String::new());
+    template["LastName"] = Value::String(String::new());
+
+    info!("Saving Character {} as template {}", id, name);
+    fs::create_dir_all(templates_dir())?;
+    write_json_atomically(&file_path, &template, get_settings().pretty_json)
+}
+
+/// Removes the template `name`.
+pub fn delete_template(name: &str) -> Result<(), std::io::Error> {
+    warn!("Deleting template {}", name);
+    fs::remove_file(template_path(name)?)
+}
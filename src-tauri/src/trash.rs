@@ -0,0 +1,116 @@
+use std::{
+    fs,
+    path::Path,
+};
+
+use chrono::{Local, NaiveDateTime};
+use log::{info, warn};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::paths::data_path;
+use crate::utils::move_file;
+
+const TRASH_DIR: &str = ".trash";
+const TIMESTAMP_FORMAT: &str = "%Y%m%d%H%M%S%3f";
+
+/// Returns the trash directory, inside the data directory.
+fn trash_dir() -> String {
+    data_path(TRASH_DIR)
+}
+
+/// A deleted character waiting in the trash.
+#[derive(Debug, Serialize)]
+pub struct TrashedCharacter {
+    file_name: String,
+    first_name: String,
+    last_name: String,
+    deleted_at: String,
+}
+
+/// Splits a trashed file name in the character file stem and its deletion time.
+fn parse_trashed_name(file_name: &str) -> Option<(&str, NaiveDateTime)> {
+    let (stem, timestamp) = file_name.strip_suffix(".json")?.rsplit_once('_')?;
+    let deleted_at = NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT).ok()?;
+    Some((stem, deleted_at))
+}
+
+/// Moves the character file at `file_path` into the trash.
+///
+/// The deletion time is appended to the trashed file name, so the same character can be
+/// deleted several times without overwriting earlier deletions.
+pub fn trash_character_file(file_path: &str) -> Result<(), std::io::Error> {
+    let stem = Path::new(file_path).file_stem().unwrap_or_default().to_string_lossy().into_owned();
+    fs::create_dir_all(trash_dir())?;
+
+    let trashed_name = format!("{}_{}.json", stem, Local::now().format(TIMESTAMP_FORMAT));
+    move_file(file_path, Path::new(&trash_dir()).join(&trashed_name))?;
+    info!("Moved {} to the trash as {}", file_path, trashed_name);
+    Ok(())
+}
+
+/// Returns the path of the trashed file named `file_name`, making sure it is in the trash.
+pub fn trashed_path(file_name: &str) -> Result<String, std::io::Error> {
+    match parse_trashed_name(file_name) {
+        Some(_) if !file_name.contains(['/', '\\']) => Ok(format!("{}/{}", trash_dir(), file_name)),
+        _ => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{} is not a trashed character", file_name),
+        )),
+    }
+}
+
+/// Lists the characters in the trash, most recently deleted first.
+pub fn list_trashed() -> Result<Vec<TrashedCharacter>, std::io::Error> {
+    if !Path::new(&trash_dir()).exists() {
+        return Ok(vec![]);
+    }
+
+    let mut trashed: Vec<TrashedCharacter> = vec![];
+    for entry in fs::read_dir(trash_dir())? {
+        let path = entry?.path();
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let deleted_at = match parse_trashed_name(&file_name) {
+            Some((_, deleted_at)) => deleted_at,
+            None => continue,
+        };
+
+        let json: Value = match fs::read_to_string(&path).map(|content| serde_json::from_str(&content)) {
+            Ok(Ok(json)) => json,
+            _ => {
+                warn!("Skipping unreadable trashed file {}", file_name);
+                continue;
+            },
+        };
+        trashed.push(TrashedCharacter {
+            first_name: json["FirstName"].as_str().unwrap_or_default().to_string(),
+            last_name: json["LastName"].as_str().unwrap_or_default().to_string(),
+            deleted_at: deleted_at.format("%d-%m-%Y %H:%M:%S").to_string(),
+            file_name,
+        });
+    }
+    trashed.sort_by(|a, b| b.file_name.cmp(&a.file_name));
+    Ok(trashed)
+}
+
+/// Permanently removes every character in the trash.
+///
+/// # Returns
+///
+/// The amount of characters removed.
+pub fn purge_trash() -> Result<usize, std::io::Error> {
+    if !Path::new(&trash_dir()).exists() {
+        return Ok(0);
+    }
+
+    let mut purged = 0;
+    for entry in fs::read_dir(trash_dir())? {
+        let path = entry?.path();
+        if path.is_file() {
+            fs::remove_file(&path)?;
+            purged += 1;
+        }
+    }
+    warn!("Purged {} characters from the trash", purged);
+    Ok(purged)
+}
@@ -1,16 +1,73 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
+
+use serde::Serialize;
+
+use crate::watcher::expect_change;
+
+/// Serializes `value` as JSON into the file at `file_path`, atomically.
+///
+/// The data is first written to a temporary file next to the target, flushed to disk and only
+/// then renamed over the original. A serialization error or a crash halfway through the write
+/// therefore leaves the previous file untouched instead of truncating it.
+///
+/// When `pretty` is set the JSON is indented, one key per line.
+pub fn write_json_atomically<T: Serialize + ?Sized>(file_path: &str, value: &T, pretty: bool) -> Result<(), std::io::Error> {
+    let temp_path = format!("{}.tmp", file_path);
+    expect_change(file_path);
+
+    let result = File::create(&temp_path).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        match pretty {
+            true => serde_json::to_writer_pretty(&mut writer, value)?,
+            false => serde_json::to_writer(&mut writer, value)?,
+        }
+        writer.flush()?;
+        writer.into_inner()?.sync_all()
+    });
+
+    match result.and_then(|_| std::fs::rename(&temp_path, file_path)) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            let _ = std::fs::remove_file(&temp_path);
+            Err(e)
+        },
+    }
+}
+
+/// Moves the file at `from` to `to`.
+///
+/// Falls back to copying and removing the original when a plain rename isn't possible, e.g.
+/// when the characters directory and the data directory are on different drives.
+pub fn move_file<P: AsRef<std::path::Path>, Q: AsRef<std::path::Path>>(from: P, to: Q) -> Result<(), std::io::Error> {
+    expect_change(&from);
+    expect_change(&to);
+    if std::fs::rename(&from, &to).is_ok() {
+        return Ok(());
+    }
+    std::fs::copy(&from, &to)?;
+    std::fs::remove_file(&from)
+}
+
 #[tauri::command]
 pub fn open_explorer() {
     #[cfg(target_os = "linux")] {
         std::process::Command::new("xdg-open")
-        .arg("./characters")
+        .arg(crate::settings::characters_dir())
         .spawn()
         .expect("Failed to open file explorer");
     }
 
     #[cfg(target_os = "windows")]
     {
+        let dir = crate::settings::characters_dir().replace('/', "\\");
         std::process::Command::new("explorer")
-        .arg(".\\characters")
+        .arg(match std::path::Path::new(&dir).is_relative() {
+            true => format!(".\\{}", dir),
+            false => dir,
+        })
         .spawn()
         .expect("Failed to open file explorer");
     }
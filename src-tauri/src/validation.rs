@@ -0,0 +1,455 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::customization::{gender_species_of, BEARD_FIELD, WINGS_FIELD};
+use crate::model::{AccessorySlot, MODELS};
+use crate::database::*;
+use crate::error::DatabaseError;
+use crate::lock::CharacterInUse;
+
+/// Fields every character file must contain as JSON strings.
+const STRING_FIELDS: [&str; 6] = [
+    "FirstName",
+    "LastName",
+    "PlayerHair",
+    "Skintone",
+    "FacePaint",
+    "HumanBeardsPixieWings",
+];
+
+/// Fields every character file must contain as JSON numbers.
+const NUMBER_FIELDS: [&str; 4] = [
+    "PlayerGUID",
+    "PlayerModel",
+    "HairColor",
+    "EyeColor",
+];
+
+/// Tables added to the database after the first data packs. The fields they back are only
+/// checked if the database has them, so an older data pack still lets every other field be
+/// edited, while `check_schema` reports the missing tables.
+const LATER_TABLES: [&str; 14] = [
+    "BodyTypes",
+    "Body_Type",
+    "Voices",
+    "Titles",
+    "Classes",
+    "Accessories",
+    "Eye_Shape",
+    "Tails",
+    "Ears",
+    "Markings",
+    "Eyebrows",
+    "Hair_Highlight_Color",
+    "Wing_Color",
+    "FacePaint_Color",
+];
+
+/// Returns the `LATER_TABLES` the database `db` lacks.
+pub fn missing_later_tables(db: &Database) -> Result<Vec<&'static str>, DatabaseError> {
+    let conn = db.connection()?;
+    let mut missing: Vec<&'static str> = vec![];
+    for table in LATER_TABLES {
+        if !has_table(&conn, table)? {
+            missing.push(table);
+        }
+    }
+    Ok(missing)
+}
+
+/// Kind of problem found in a character file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// The file is not a JSON object at all.
+    NotAnObject,
+    /// A required field is missing or has the wrong JSON type.
+    InvalidField,
+    /// `PlayerModel` is not one of the known gender/species models.
+    UnknownModel,
+    /// A color id outside the range of colors the database knows.
+    OutOfRange,
+    /// A cosmetic the database doesn't know.
+    UnknownCosmetic,
+    /// A cosmetic that exists, but not for the gender and species of the character.
+    WrongModel,
+    /// A cosmetic that can't be worn together with another cosmetic of the character, see
+    /// `Constraint`.
+    Incompatible,
+}
+
+/// A problem found in a character file, in a form the UI can act upon.
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationIssue {
+    pub field: String,
+    pub kind: IssueKind,
+    pub message: String,
+}
+
+impl ValidationIssue {
+    fn new(field: &str, kind: IssueKind, message: String) -> Self {
+        ValidationIssue { field: field.to_string(), kind, message }
+    }
+}
+
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Error of a change rejected because it would leave the character with values the game can't
+/// render.
+#[derive(Debug, Clone, Serialize)]
+pub struct InvalidAppearance {
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl std::fmt::Display for InvalidAppearance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let messages: Vec<&str> = self.issues.iter().map(|issue| issue.message.as_str()).collect();
+        write!(f, "{}", messages.join(", "))
+    }
+}
+
+impl std::error::Error for InvalidAppearance {}
+
+/// Error returned to the frontend by the commands changing the appearance of a character.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AppearanceError {
+    /// The change was rejected, `issues` tells which of the values can't be rendered.
+    InvalidAppearance { issues: Vec<ValidationIssue> },
+    /// The character file is loaded by a running server and was left untouched.
+    CharacterInUse { file_name: String },
+    /// The character could not be read or written.
+    Failed { message: String },
+}
+
+impl From<std::io::Error> for AppearanceError {
+    fn from(e: std::io::Error) -> Self {
+        if let Some(invalid) = e.get_ref().and_then(|inner| inner.downcast_ref::<InvalidAppearance>()) {
+            return AppearanceError::InvalidAppearance { issues: invalid.issues.clone() };
+        }
+        if let Some(in_use) = e.get_ref().and_then(|inner| inner.downcast_ref::<CharacterInUse>()) {
+            return AppearanceError::CharacterInUse { file_name: in_use.file_name.clone() };
+        }
+        AppearanceError::Failed { message: e.to_string() }
+    }
+}
+
+/// Describes a color id missing from `colors`, telling apart ids outside the known range.
+fn color_issue(field: &str, color: u64, colors: &[u64]) -> Option<ValidationIssue> {
+    if colors.contains(&color) {
+        return None;
+    }
+    let in_range = matches!(
+        (colors.iter().min(), colors.iter().max()),
+        (Some(min), Some(max)) if (*min..=*max).contains(&color)
+    );
+    Some(match in_range {
+        true => ValidationIssue::new(field, IssueKind::UnknownCosmetic, format!("{} {} is not a known color", field, color)),
+        false => ValidationIssue::new(field, IssueKind::OutOfRange, format!("{} {} is out of the range of known colors", field, color)),
+    })
+}
+
+/// Returns the text of the field `field` of the character `json`, `None` if it is missing or empty.
+fn field_text(json: &Value, field: &str) -> Option<String> {
+    match &json[field] {
+        Value::String(text) if !text.is_empty() => Some(text.clone()),
+        Value::Number(number) => Some(number.to_string()),
+        _ => None,
+    }
+}
+
+/// Returns `true` if `text` matches `pattern`, where `*` matches any text.
+fn matches_pattern(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let mut rest = match text.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let parts: Vec<&str> = parts.collect();
+    match parts.split_last() {
+        None => rest.is_empty(),
+        Some((last, middle)) => {
+            for part in middle {
+                match rest.find(part) {
+                    Some(start) => rest = &rest[start + part.len()..],
+                    None => return false,
+                }
+            }
+            rest.len() >= last.len() && rest.ends_with(last)
+        },
+    }
+}
+
+/// Returns `IssueKind::WrongModel` if `offered` tells that some model, called with its species
+/// and gender, offers a cosmetic missing for the character, `IssueKind::UnknownCosmetic`
+/// otherwise.
+fn wrong_model_kind<F>(mut offered: F) -> Result<IssueKind, DatabaseError>
+where
+    F: FnMut(&str, &str) -> Result<bool, DatabaseError>,
+{
+    for model in MODELS {
+        if offered(model.species.as_str(), model.gender.as_str())? {
+            return Ok(IssueKind::WrongModel);
+        }
+    }
+    Ok(IssueKind::UnknownCosmetic)
+}
+
+/// Returns an issue for each of the two fields of every constraint the character `json` breaks,
+/// so the change of either field is refused.
+fn constraint_issues(json: &Value, constraints: &[Constraint]) -> Vec<ValidationIssue> {
+    let mut issues: Vec<ValidationIssue> = vec![];
+    for constraint in constraints {
+        let (value, other_value) = match (field_text(json, &constraint.field), field_text(json, &constraint.other_field)) {
+            (Some(value), Some(other_value)) => (value, other_value),
+            _ => continue,
+        };
+        if !matches_pattern(&constraint.value, &value) || !matches_pattern(&constraint.other_value, &other_value) {
+            continue;
+        }
+        for (field, value, other_field, other_value) in [
+            (&constraint.field, &value, &constraint.other_field, &other_value),
+            (&constraint.other_field, &other_value, &constraint.field, &value),
+        ] {
+            let message = match &constraint.reason {
+                Some(reason) => format!("{} {} can't be combined with {} {}: {}", field, value, other_field, other_value, reason),
+                None => format!("{} {} can't be combined with {} {}", field, value, other_field, other_value),
+            };
+            issues.push(ValidationIssue::new(field, IssueKind::Incompatible, message));
+        }
+    }
+    issues
+}
+
+/// Checks the character `json` against the expected character schema and the cosmetics stored
+/// in the database `db`, disabled ones included, so hiding a cosmetic doesn't invalidate the
+/// characters wearing it, and against the constraints of the database, see `Constraint`.
+///
+/// Fields backed by one of `LATER_TABLES` the database lacks are not checked.
+///
+/// # Returns
+///
+/// Every problem found, empty when the character is valid, or a `DatabaseError` if the
+/// database could not be queried.
+pub fn validate_character(json: &Value, db: &Database) -> Result<Vec<ValidationIssue>, DatabaseError> {
+    let db = &db.including_disabled();
+    let mut issues: Vec<ValidationIssue> = vec![];
+
+    if !json.is_object() {
+        issues.push(ValidationIssue::new("", IssueKind::NotAnObject, "Character file is not a JSON object".to_string()));
+        return Ok(issues);
+    }
+    for field in STRING_FIELDS {
+        if !json[field].is_string() {
+            issues.push(ValidationIssue::new(field, IssueKind::InvalidField, format!("{} is missing or is not a string", field)));
+        }
+    }
+    for field in NUMBER_FIELDS {
+        if !json[field].is_u64() {
+            issues.push(ValidationIssue::new(field, IssueKind::InvalidField, format!("{} is missing or is not a number", field)));
+        }
+    }
+
+    let (gender, species) = gender_species_of(json["PlayerModel"].as_u64().unwrap_or_default());
+    if gender.is_empty() {
+        issues.push(ValidationIssue::new("PlayerModel", IssueKind::UnknownModel, format!("PlayerModel {} is not a known model", json["PlayerModel"])));
+        return Ok(issues);
+    }
+
+    let missing_tables = missing_later_tables(db)?;
+    let has = |table: &str| !missing_tables.contains(&table);
+
+    if let Some(hair) = json["PlayerHair"].as_str() {
+        if !get_hairs(db, gender, None)?.iter().any(|h| h.addr == hair) {
+            let kind = wrong_model_kind(|_, other_gender| Ok(get_hairs(db, other_gender, None)?.iter().any(|h| h.addr == hair)))?;
+            issues.push(ValidationIssue::new("PlayerHair", kind, format!("PlayerHair {} is not available for this model", hair)));
+        }
+    }
+    if let Some(color) = json["HairColor"].as_u64() {
+        let colors: Vec<u64> = get_hair_color(db, None)?.iter().map(|c| c.color as u64).collect();
+        issues.extend(color_issue("HairColor", color, &colors));
+    }
+    if let Some(head) = json["PlayerHead"].as_str() {
+        if !get_face_shapes(db, species, gender, None)?.iter().any(|f| f.addr == head) {
+            let kind = wrong_model_kind(|other_species, other_gender| {
+                Ok(get_face_shapes(db, other_species, other_gender, None)?.iter().any(|f| f.addr == head))
+            })?;
+            issues.push(ValidationIssue::new("PlayerHead", kind, format!("PlayerHead {} is not available for this model", head)));
+        }
+    }
+    if has("BodyTypes") && (json["BodyType"].is_u64() || json["BodyScale"].is_number()) {
+        let ranges = get_body_ranges(db)?;
+        let range = ranges.iter().find(|range| range.species == species);
+        if let Some(body_type) = json["BodyType"].as_u64() {
+            if !range.map_or(false, |range| (u64::from(range.min_body_type)..=u64::from(range.max_body_type)).contains(&body_type)) {
+                issues.push(ValidationIssue::new("BodyType", IssueKind::OutOfRange, format!("BodyType {} is out of the range allowed for {}", body_type, species)));
+            }
+        }
+        if let Some(scale) = json["BodyScale"].as_f64() {
+            if !range.map_or(false, |range| (range.min_scale..=range.max_scale).contains(&scale)) {
+                issues.push(ValidationIssue::new("BodyScale", IssueKind::OutOfRange, format!("BodyScale {} is out of the range allowed for {}", scale, species)));
+            }
+        }
+    }
+    if let (Some(voice), true) = (json["Voice"].as_u64(), has("Voices")) {
+        if !get_voices(db, species, gender, None)?.iter().any(|v| v.id as u64 == voice) {
+            let kind = wrong_model_kind(|other_species, other_gender| {
+                Ok(get_voices(db, other_species, other_gender, None)?.iter().any(|v| v.id as u64 == voice))
+            })?;
+            issues.push(ValidationIssue::new("Voice", kind, format!("Voice {} is not available for this model", voice)));
+        }
+    }
+    if let (Some(title), true) = (json["PlayerTitleStruct"]["Title"].as_u64(), has("Titles")) {
+        if title != 0 && !get_titles(db, None)?.iter().any(|t| t.id == title) {
+            issues.push(ValidationIssue::new("PlayerTitleStruct", IssueKind::UnknownCosmetic, format!("Title {} is not a known title", title)));
+        }
+    }
+    if let (Some(class), true) = (json["Class"].as_u64(), has("Classes")) {
+        if !get_classes(db, None)?.iter().any(|c| c.id == class) {
+            issues.push(ValidationIssue::new("Class", IssueKind::UnknownCosmetic, format!("Class {} is not a known class", class)));
+        }
+    }
+    for slot in AccessorySlot::ALL {
+        let field = slot.field();
+        if let (Some(item), true) = (json[field].as_str(), has("Accessories")) {
+            if !item.is_empty() && !get_accessories(db, slot.as_str(), species, gender, None)?.iter().any(|a| a.addr == item) {
+                let kind = wrong_model_kind(|other_species, other_gender| {
+                    Ok(get_accessories(db, slot.as_str(), other_species, other_gender, None)?.iter().any(|a| a.addr == item))
+                })?;
+                issues.push(ValidationIssue::new(field, kind, format!("{} {} is not available for this model", field, item)));
+            }
+        }
+    }
+    if let (Some(eye_shape), true) = (json["EyeShape"].as_str(), has("Eye_Shape")) {
+        if !eye_shape.is_empty() && !get_eye_shapes(db, species, None)?.iter().any(|e| e.addr == eye_shape) {
+            let kind = wrong_model_kind(|other_species, _| Ok(get_eye_shapes(db, other_species, None)?.iter().any(|e| e.addr == eye_shape)))?;
+            issues.push(ValidationIssue::new("EyeShape", kind, format!("EyeShape {} is not available for this species", eye_shape)));
+        }
+    }
+    if let (Some(tail), true) = (json["Tail"].as_str(), has("Tails")) {
+        if !tail.is_empty() && !get_tails(db, species, None)?.iter().any(|t| t.addr == tail) {
+            let kind = wrong_model_kind(|other_species, _| Ok(get_tails(db, other_species, None)?.iter().any(|t| t.addr == tail)))?;
+            issues.push(ValidationIssue::new("Tail", kind, format!("Tail {} is not available for this species", tail)));
+        }
+    }
+    if let (Some(ears), true) = (json["Ears"].as_str(), has("Ears")) {
+        if !ears.is_empty() && !get_ears(db, species, gender, None)?.iter().any(|e| e.addr == ears) {
+            let kind = wrong_model_kind(|other_species, other_gender| {
+                Ok(get_ears(db, other_species, other_gender, None)?.iter().any(|e| e.addr == ears))
+            })?;
+            issues.push(ValidationIssue::new("Ears", kind, format!("Ears {} are not available for this model", ears)));
+        }
+    }
+    if let (Some(markings), true) = (json["Markings"].as_array(), has("Markings")) {
+        let available = get_markings(db, species, gender, None)?;
+        for marking in markings {
+            let marking = match marking.as_u64() {
+                Some(marking) => marking,
+                None => {
+                    issues.push(ValidationIssue::new("Markings", IssueKind::InvalidField, format!("Marking {} is not a number", marking)));
+                    continue;
+                },
+            };
+            if !available.iter().any(|m| m.id as u64 == marking) {
+                let kind = wrong_model_kind(|other_species, other_gender| {
+                    Ok(get_markings(db, other_species, other_gender, None)?.iter().any(|m| m.id as u64 == marking))
+                })?;
+                issues.push(ValidationIssue::new("Markings", kind, format!("Marking {} is not available for this model", marking)));
+            }
+        }
+    }
+    if let (Some(style), true) = (json["EyebrowStyle"].as_str(), has("Eyebrows")) {
+        if !style.is_empty() && !get_eyebrows(db, gender, None)?.iter().any(|e| e.addr == style) {
+            let kind = wrong_model_kind(|_, other_gender| Ok(get_eyebrows(db, other_gender, None)?.iter().any(|e| e.addr == style)))?;
+            issues.push(ValidationIssue::new("EyebrowStyle", kind, format!("EyebrowStyle {} is not available for this model", style)));
+        }
+    }
+    if let Some(color) = json["EyebrowColor"].as_u64() {
+        let colors: Vec<u64> = get_hair_color(db, None)?.iter().map(|c| c.color as u64).collect();
+        issues.extend(color_issue("EyebrowColor", color, &colors));
+    }
+    if let (Some(color), true) = (json["HairHighlightColor"].as_u64(), has("Hair_Highlight_Color")) {
+        let colors: Vec<u64> = get_hair_highlight_colors(db, None)?.iter().map(|c| c.color as u64).collect();
+        issues.extend(color_issue("HairHighlightColor", color, &colors));
+    }
+    if let (Some(color), true) = (json["WingColor"].as_u64(), has("Wing_Color")) {
+        let colors: Vec<u64> = get_wing_colors(db, None)?.iter().map(|c| c.color as u64).collect();
+        issues.extend(color_issue("WingColor", color, &colors));
+    }
+    if let (Some(color), true) = (json["FacePaintColor"].as_u64(), has("FacePaint_Color")) {
+        let colors: Vec<u64> = get_facepaint_colors(db, None)?.iter().map(|c| c.color as u64).collect();
+        issues.extend(color_issue("FacePaintColor", color, &colors));
+    }
+    if let Some(opacity) = json["FacePaintOpacity"].as_f64() {
+        if !(0.0..=1.0).contains(&opacity) {
+            issues.push(ValidationIssue::new("FacePaintOpacity", IssueKind::OutOfRange, format!("FacePaintOpacity {} is not between 0 and 1", opacity)));
+        }
+    }
+    if let Some(color) = json["EyeColor"].as_u64() {
+        let colors: Vec<u64> = get_eye_color(db, None)?.iter().map(|c| c.color as u64).collect();
+        issues.extend(color_issue("EyeColor", color, &colors));
+    }
+    if let Some(skintone) = json["Skintone"].as_str() {
+        if !get_skintones(db, species, None)?.iter().any(|s| s.addr == skintone) {
+            let kind = wrong_model_kind(|other_species, _| Ok(get_skintones(db, other_species, None)?.iter().any(|s| s.addr == skintone)))?;
+            issues.push(ValidationIssue::new("Skintone", kind, format!("Skintone {} is not available for this species", skintone)));
+        }
+    }
+    if let Some(facepaint) = json["FacePaint"].as_str() {
+        if !get_facepaints(db, None)?.iter().any(|f| f.texture_alias == facepaint) {
+            issues.push(ValidationIssue::new("FacePaint", IssueKind::UnknownCosmetic, format!("FacePaint {} is not a known face paint", facepaint)));
+        }
+    }
+    if let Some(extra) = json["HumanBeardsPixieWings"].as_str() {
+        if !extra.is_empty() && !get_wings_by_gender_species(db, gender, species, None)?.iter().any(|e| e.addr == extra) {
+            let kind = wrong_model_kind(|other_species, other_gender| {
+                Ok(get_wings_by_gender_species(db, other_gender, other_species, None)?.iter().any(|e| e.addr == extra))
+            })?;
+            issues.push(ValidationIssue::new("HumanBeardsPixieWings", kind, format!("HumanBeardsPixieWings {} is not available for this model", extra)));
+        }
+    }
+
+    if let Some(beard) = json[BEARD_FIELD].as_str() {
+        if !beard.is_empty() && !get_facial_hair(db, "m", "human", None)?.iter().any(|e| e.addr == beard) {
+            issues.push(ValidationIssue::new(BEARD_FIELD, IssueKind::UnknownCosmetic, format!("{} {} is not a known beard", BEARD_FIELD, beard)));
+        }
+    }
+    if let Some(wings) = json[WINGS_FIELD].as_str() {
+        let mut known = false;
+        for other_gender in ["m", "f"] {
+            known |= get_wings_by_gender_species(db, other_gender, "fairy", None)?.iter().any(|e| e.addr == wings);
+        }
+        if !wings.is_empty() && !known {
+            issues.push(ValidationIssue::new(WINGS_FIELD, IssueKind::UnknownCosmetic, format!("{} {} are not known wings", WINGS_FIELD, wings)));
+        }
+    }
+
+    issues.extend(constraint_issues(json, &get_constraints(db)?));
+    Ok(issues)
+}
+
+/// Checks the `fields` of the character `json` against the database `db`, before
+/// they are written.
+///
+/// Problems in other fields are ignored, so a change isn't refused because of a value that was
+/// already in the file.
+///
+/// # Returns
+///
+/// An error of kind `std::io::ErrorKind::InvalidInput` wrapping an `InvalidAppearance` if any of
+/// `fields` holds a value the game can't render.
+pub fn check_fields(json: &Value, db: &Database, fields: &[&str]) -> Result<(), std::io::Error> {
+    let issues: Vec<ValidationIssue> = validate_character(json, db)?
+        .into_iter()
+        .filter(|issue| fields.contains(&issue.field.as_str()))
+        .collect();
+    match issues.is_empty() {
+        true => Ok(()),
+        false => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput, InvalidAppearance { issues })),
+    }
+}
@@ -0,0 +1,158 @@
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use chrono::{Local, NaiveDateTime};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::customization::UPDATED_AT_FIELD;
+use crate::paths::data_path;
+
+const VERSION_DIR: &str = "versions";
+const ID_FORMAT: &str = "%Y%m%d%H%M%S%3f";
+
+/// Amount of versions kept per character, older ones are removed after every save.
+const MAX_VERSIONS: usize = 50;
+
+/// A saved version as stored on disk, the character content together with its metadata.
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredVersion {
+    summary: String,
+    character: Value,
+}
+
+/// A saved version of a character, as listed to the user.
+#[derive(Debug, Serialize)]
+pub struct Version {
+    id: String,
+    saved_at: String,
+    summary: String,
+}
+
+/// Returns the versions directory, inside the data directory.
+fn versions_dir() -> String {
+    data_path(VERSION_DIR)
+}
+
+/// Returns the versions folder of the character `character_id`.
+fn version_dir(character_id: &str) -> String {
+    format!("{}/{}", versions_dir(), character_id)
+}
+
+/// Describes what changed between `previous` and `current`.
+fn summarize(previous: Option<&Value>, current: &Value) -> String {
+    let previous = match previous {
+        Some(previous) => previous,
+        None => return "Created".to_string(),
+    };
+
+    let mut changed: Vec<&str> = vec![];
+    if let (Some(previous), Some(current)) = (previous.as_object(), current.as_object()) {
+        for (key, value) in current {
+            // Changes on every write, so it would be listed in every summary.
+            if key == UPDATED_AT_FIELD {
+                continue;
+            }
+            if previous.get(key) != Some(value) {
+                changed.push(key);
+            }
+        }
+        for key in previous.keys() {
+            if !current.contains_key(key) {
+                changed.push(key);
+            }
+        }
+    }
+
+    match changed.is_empty() {
+        true => "No changes".to_string(),
+        false => format!("Changed {}", changed.join(", ")),
+    }
+}
+
+/// Returns the ids of every version of the character `character_id`, oldest first.
+fn versions_of(character_id: &str) -> Result<Vec<String>, std::io::Error> {
+    let dir = version_dir(character_id);
+    if !Path::new(&dir).exists() {
+        return Ok(vec![]);
+    }
+
+    let mut ids: Vec<String> = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().map_or(false, |extension| extension == "json") {
+            ids.push(path.file_stem().unwrap_or_default().to_string_lossy().into_owned());
+        }
+    }
+    ids.sort();
+    Ok(ids)
+}
+
+/// Records `current`, just saved for the character `character_id`, as a new version.
+///
+/// The version carries a summary of the fields that changed since `previous`, the content the
+/// file had before the save. Only the last `MAX_VERSIONS` versions are kept.
+pub fn record_version(character_id: &str, previous: Option<&Value>, current: &Value) -> Result<(), std::io::Error> {
+    if character_id.is_empty() {
+        return Ok(());
+    }
+    let dir = version_dir(character_id);
+    fs::create_dir_all(&dir)?;
+
+    let id = Local::now().format(ID_FORMAT).to_string();
+    let version = StoredVersion {
+        summary: summarize(previous, current),
+        character: current.clone(),
+    };
+    let mut writer = BufWriter::new(File::create(format!("{}/{}.json", dir, id))?);
+    serde_json::to_writer(&mut writer, &version)?;
+    writer.flush()?;
+    info!("Recorded version {} of {}: {}", id, character_id, version.summary);
+
+    let ids = versions_of(character_id)?;
+    if ids.len() > MAX_VERSIONS {
+        for old in &ids[..ids.len() - MAX_VERSIONS] {
+            if let Err(e) = fs::remove_file(format!("{}/{}.json", dir, old)) {
+                warn!("Could not remove old version {} due to {:#?}", old, e);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reads the version `id` of the character `character_id`.
+fn read_version(character_id: &str, id: &str) -> Result<StoredVersion, std::io::Error> {
+    if NaiveDateTime::parse_from_str(id, ID_FORMAT).is_err() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{} is not a version id", id),
+        ));
+    }
+    let file = File::open(format!("{}/{}.json", version_dir(character_id), id))?;
+    Ok(serde_json::from_reader(file)?)
+}
+
+/// Lists the saved versions of the character `character_id`, newest first.
+pub fn list_versions(character_id: &str) -> Result<Vec<Version>, std::io::Error> {
+    let mut versions: Vec<Version> = vec![];
+    for id in versions_of(character_id)?.into_iter().rev() {
+        let saved_at = match NaiveDateTime::parse_from_str(&id, ID_FORMAT) {
+            Ok(saved_at) => saved_at.format("%d-%m-%Y %H:%M:%S").to_string(),
+            Err(_) => continue,
+        };
+        match read_version(character_id, &id) {
+            Ok(version) => versions.push(Version { id, saved_at, summary: version.summary }),
+            Err(e) => warn!("Skipping version {} of {} due to {:#?}", id, character_id, e),
+        }
+    }
+    Ok(versions)
+}
+
+/// Returns the character content saved in the version `id` of the character `character_id`.
+pub fn load_version(character_id: &str, id: &str) -> Result<Value, std::io::Error> {
+    Ok(read_version(character_id, id)?.character)
+}
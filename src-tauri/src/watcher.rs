@@ -0,0 +1,129 @@
+use std::{
+    fs,
+    path::Path,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use log::{error, info, warn};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::customization::EditSessions;
+use crate::index::{is_character_file, rebuild_index};
+use crate::settings::characters_dir;
+
+/// Event emitted to the frontend whenever a character file is added, changed or removed by
+/// another program.
+pub const CHARACTER_FILE_CHANGED_EVENT: &str = "character-file-changed";
+
+/// Time during which changes to a file this app wrote are not reported, the file system can
+/// deliver the events of a write with some delay.
+const OWN_WRITE_WINDOW: Duration = Duration::from_secs(2);
+
+/// Files this app wrote recently, by file name, with the time of the write.
+static OWN_WRITES: Mutex<Vec<(String, Instant)>> = Mutex::new(Vec::new());
+
+/// What happened to a character file.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Changed,
+    Removed,
+}
+
+/// Payload of `CHARACTER_FILE_CHANGED_EVENT`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExternalChange {
+    kind: ChangeKind,
+    file_name: String,
+    /// Id of the changed character, `None` for removed files.
+    id: Option<String>,
+    /// Whether the character is being edited in a session, whose save would overwrite the
+    /// external change.
+    conflict: bool,
+}
+
+/// Records that this app is about to write, move or remove the file at `path`, so the watcher
+/// doesn't report it as an external change.
+pub fn expect_change<P: AsRef<Path>>(path: P) {
+    let file_name = match path.as_ref().file_name() {
+        Some(file_name) => file_name.to_string_lossy().into_owned(),
+        None => return,
+    };
+    let mut own_writes = OWN_WRITES.lock().unwrap();
+    own_writes.retain(|(_, written_at)| written_at.elapsed() < OWN_WRITE_WINDOW);
+    own_writes.push((file_name, Instant::now()));
+}
+
+/// Returns `true` if this app wrote the file `file_name` within `OWN_WRITE_WINDOW`.
+fn is_own_write(file_name: &str) -> bool {
+    OWN_WRITES.lock().unwrap()
+        .iter()
+        .any(|(written, written_at)| written == file_name && written_at.elapsed() < OWN_WRITE_WINDOW)
+}
+
+/// Watches the characters directory for changes made by other programs, e.g. a text editor or
+/// a server tool, and reports them to the frontend.
+#[derive(Default)]
+pub struct CharacterWatcher(Mutex<Option<RecommendedWatcher>>);
+
+impl CharacterWatcher {
+    /// Starts watching the current characters directory, replacing the previous watch, e.g.
+    /// after the user picked another directory.
+    pub fn watch(&self, app: AppHandle) -> Result<(), std::io::Error> {
+        let dir = characters_dir();
+        fs::create_dir_all(&dir)?;
+
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+            Ok(event) => handle_event(&app, event),
+            Err(e) => warn!("Error while watching the characters directory, due to {:#?}", e),
+        }).map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+        watcher
+            .watch(Path::new(&dir), RecursiveMode::NonRecursive)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+
+        info!("Watching characters directory {}", dir);
+        *self.0.lock().unwrap() = Some(watcher);
+        Ok(())
+    }
+}
+
+/// Reports every character file of `event` not written by this app to the frontend.
+fn handle_event(app: &AppHandle, event: Event) {
+    let kind = match event.kind {
+        EventKind::Create(_) => ChangeKind::Added,
+        EventKind::Modify(_) => ChangeKind::Changed,
+        EventKind::Remove(_) => ChangeKind::Removed,
+        _ => return,
+    };
+
+    for path in event.paths.iter().filter(|path| is_character_file(path)) {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        if is_own_write(&file_name) {
+            continue;
+        }
+        // Renames show up as changes, so the kind is taken from whether the file still exists.
+        let kind = match (kind, path.exists()) {
+            (ChangeKind::Changed, false) => ChangeKind::Removed,
+            (kind, _) => kind,
+        };
+        info!("Character file {} was {:?} externally", file_name, kind);
+
+        let id = match rebuild_index() {
+            Ok(characters) => characters.into_iter().find(|character| character.file_name == file_name).map(|character| character.id),
+            Err(e) => {
+                warn!("Could not rebuild the index after an external change, due to {:#?}", e);
+                None
+            },
+        };
+        let conflict = id.as_deref().map_or(false, |id| app.state::<EditSessions>().is_open(id));
+
+        let change = ExternalChange { kind, file_name, id, conflict };
+        if let Err(e) = app.emit_all(CHARACTER_FILE_CHANGED_EVENT, change) {
+            error!("Error ocurred while emitting {}, due to {:#?}", CHARACTER_FILE_CHANGED_EVENT, &e);
+        }
+    }
+}
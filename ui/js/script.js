@@ -1,132 +1,105 @@
 const { invoke } = window.__TAURI__.tauri;
 document.addEventListener('DOMContentLoaded', (event) => {
-    function mapGenderRaceToValues(genderRaceValue) {
-        switch (genderRaceValue) {
-            case '60':
-                return { gender: "f", species: "human" };
-            case '1':
-                return { gender: "m", species: "human" };
-            case '61':
-                return { gender: "f", species: "fairy" };
-            case '2':
-                return { gender: "m", species: "fairy" };
-            case '0':
-                return { gender: "", species: "" };
-        }
-    }
-    function mapGenderRaceToGender(genderRaceValue) {
-        switch (genderRaceValue) {
-            case '60':
-                return 'f';
-            case '1':
-                return 'm';
-            case '61':
-                return 'f';
-            case '2':
-                return 'm';
-            case '0':
-                return '';
-        }
-    }
-
     const wingsSelect = document.getElementById('extras');
 
-    // Capturar o evento de mudança no elemento genderRace
-    var genderrace = document.getElementById('genderrace');
-    genderrace.addEventListener('change', function () {
-        const genderRaceValue = genderRace.value;
-        var extras = document.getElementById('Extras');
-        if (genderRaceValue === '60') {
-            extras.hidden = true;
-            alert("There is no available Extras for Human Females!");
-            return 0;
-        }
-        extras.hidden = false;
+    // Replaces the options of the select `id` with one option per item of `items`, keeping the
+    // current choice if it is still offered.
+    function fillSelect(id, items, value, text) {
+        const select = document.getElementById(id);
+        const selected = select.value;
+        select.innerHTML = '';
+        items.forEach(item => {
+            var option = document.createElement('option');
+            option.value = value(item);
+            option.text = text(item);
+            option.selected = option.value === selected;
+            select.appendChild(option);
+        });
+    }
 
-        const { gender, species } = mapGenderRaceToValues(genderRaceValue);
+    // Colors the options of the select `id` with the swatches of the palette `kind`, options
+    // without a known color keep the default look.
+    function paintSwatches(id, kind) {
+        invoke('get_palette', { kind }).then((palette) => {
+            const colors = new Map(palette.map(color => [String(color.color), color.hex]));
+            Array.from(document.getElementById(id).options).forEach(option => {
+                if (colors.has(option.value)) {
+                    option.style.backgroundColor = colors.get(option.value);
+                }
+            });
+        }).catch(reportDatabaseError);
+    }
 
-        wingsSelect.innerHTML = '';
+    // Returns the text of the option of `cosmetic`, marking cosmetics a server unlocks separately,
+    // e.g. "Mohawk (membership)".
+    function cosmeticText(cosmetic) {
+        return cosmetic.unlock_flag ? `${cosmetic.name} (${cosmetic.unlock_flag})` : cosmetic.name;
+    }
 
-        invoke('model_extras', { gender, species }).then((payload) => {
-            const wingsArray = payload;
+    // Fills every option list for the model `gender` and `species` from a single command.
+    function loadCustomizationOptions(gender, species) {
+        invoke('get_all_customization_options', { gender, species }).then((options) => {
+            fillSelect('hairtype', options.hairs, hair => hair.addr, cosmeticText);
+            fillSelect('haircolor', options.hair_colors, haircolor => haircolor.color, haircolor => haircolor.name);
+            fillSelect('eyecolor', options.eye_colors, paint => paint.name, paint => paint.color);
+            paintSwatches('haircolor', 'hair');
+            paintSwatches('eyecolor', 'eye');
+            fillSelect('facepaint', options.facepaints, paint => paint.texture_alias, paint => paint.texture_alias);
+            fillSelect('skintone', options.skintones, skintone => skintone.addr, skintone => skintone.name);
+            fillSelect('extras', options.extras, wing => wing.addr, cosmeticText);
+
+            var extras = document.getElementById('Extras');
+            extras.hidden = gender !== '' && options.extras.length === 0;
+            if (extras.hidden) {
+                alert("There is no available Extras for this model!");
+            }
 
             if (gender == "m" && species == "human") {
                 var defaultOption = document.createElement('option');
                 defaultOption.value = '';
                 defaultOption.text = 'Clean Shaved';
-                wingsSelect.appendChild(defaultOption);
+                wingsSelect.insertBefore(defaultOption, wingsSelect.firstChild);
             }
+        }).catch(reportDatabaseError);
+    }
 
-            wingsArray.forEach(wing => {
-                var option = document.createElement('option');
-                option.value = wing.addr;
-                option.text = wing.name;
-                wingsSelect.appendChild(option);
-            })
-        });
-    });
-
-    const modelHairType = document.getElementById('hairtype');
+    // Capturar o evento de mudança no elemento genderRace
     var genderrace = document.getElementById('genderrace');
-
     genderrace.addEventListener('change', function () {
-        modelHairType.innerHTML = '';
-        const genderRaceValue = genderRace.value;
-
-        const hairGender = mapGenderRaceToGender(genderRaceValue);
-        wingsSelect.innerHTML = '';
-
-        invoke('hair_type', { gender: hairGender }).then((payload) => {
-            const hairArray = payload;
-
-            const select = document.getElementById('hairtype');
-            hairArray.forEach(hair => {
-                const option = document.createElement('option');
-                option.value = hair.addr;
-                option.text = hair.name;
-                select.appendChild(option);
-            });
-        });
-
+        const { gender = '', species = '' } = genderrace.selectedOptions[0].dataset;
+        loadCustomizationOptions(gender, species);
     });
 
 
-    invoke('hair_color').then((payload) => {
-        const paintArray = payload;
-        const select = document.getElementById('haircolor');
+    invoke('species').then((payload) => {
+        const select = document.getElementById('genderrace');
 
-        paintArray.forEach(haircolor => {
+        payload.forEach(model => {
             var option = document.createElement('option');
-            option.value = haircolor.color;
-            option.text = haircolor.name;
+            option.value = model.model;
+            option.text = model.name;
+            option.dataset.gender = model.gender;
+            option.dataset.species = model.species;
             select.appendChild(option);
         })
-    });
-
+    }).catch(reportDatabaseError);
 
+    invoke('check_database_schema').then((issues) => {
+        if (issues.length > 0) {
+            alert(`The cosmetics database doesn't match this version of the creator:\n${issues.map(issue => issue.message).join('\n')}`);
+        }
+    }).catch(reportDatabaseError);
+
+    // Being offline shouldn't get in the way, so a failed check is only logged by the backend
+    invoke('check_database_update').then((update) => {
+        if (update.available && confirm(`A newer cosmetics database is available (version ${update.latest_version}), download it now?`)) {
+            invoke('update_database')
+                .then(() => window.location.reload())
+                .catch((error) => alert(`The cosmetics database could not be updated: ${error}`));
+        }
+    }).catch(() => {});
 
-    invoke('facepaint').then((payload) => {
-        const paintArray = payload;
-        const select = document.getElementById('facepaint');
-
-        paintArray.forEach(paint => {
-            var option = document.createElement('option');
-            option.value = paint.texture_alias;
-            option.text = paint.texture_alias;
-            select.appendChild(option);
-        })
-    });
-
-    invoke('eye_color').then((payload) => {
-        const select = document.getElementById('eyecolor');
-
-        payload.forEach(paint => {
-            var option = document.createElement('option');
-            option.value = paint.name;
-            option.text = paint.color;
-            select.appendChild(option);
-        })
-    });
+    loadCustomizationOptions('', '');
 
 });
 
@@ -135,8 +108,7 @@ var operationQueue = [];
 async function enqueueOperation(operation) {
     const operationPromise = new Promise(async (resolve, reject) => {
         try {
-            await operation();
-            resolve();
+            resolve(await operation());
         } catch (error) {
             reject(error);
         }
@@ -149,6 +121,38 @@ async function enqueueOperation(operation) {
     }
 
     operationQueue.shift();
+    return operationPromise;
+}
+
+// Shows the first database error to the user, the lists filled at startup would otherwise
+// report the same missing database once each.
+var databaseErrorReported = false;
+
+function reportDatabaseError(error) {
+    if (!databaseErrorReported) {
+        databaseErrorReported = true;
+        alert(describeError(error));
+    }
+}
+
+// Turns an error returned by a command into a message for the user.
+function describeError(error) {
+    if (error && error.kind === 'invalid_appearance') {
+        return error.issues.map(issue => issue.message).join('\n');
+    }
+    if (error && error.kind === 'slots_full') {
+        return `Every one of the ${error.max_characters} character slots is taken, delete a character first.`;
+    }
+    if (error && error.kind === 'character_in_use') {
+        return `${error.file_name} is in use by a running server, close the character in the server and try again.`;
+    }
+    if (error && error.kind === 'failed') {
+        return error.message;
+    }
+    if (error && ['database_missing', 'database_locked', 'database_error'].includes(error.kind)) {
+        return error.message;
+    }
+    return error;
 }
 
 const createBtn = document.getElementById('createBtn');
@@ -181,16 +185,28 @@ createBtn.addEventListener('click', async function () {
     createBtn.disabled = true;
 
     try {
-        await enqueueOperation(() => invoke('new_character', { username: usernameValue, surname: surnameValue }));
-        await enqueueOperation(() => invoke('set_genderace', { username: usernameValue, surname: surnameValue, genderrace: Number(genderRaceValue) }));
-        await enqueueOperation(() => invoke('set_facepaint', { username: usernameValue, surname: surnameValue, facepaint: facePaintValue }));
-        await enqueueOperation(() => invoke('set_skintone', { username: usernameValue, surname: surnameValue, newskintone: skintoneValue }));
-        await enqueueOperation(() => invoke('set_hair', { username: usernameValue, surname: surnameValue, hairtype: hairTypeValue, haircolor: Number(hairColorValue) }));
-        await enqueueOperation(() => invoke('set_eyes', { username: usernameValue, surname: surnameValue, color: Number(eyeColorValue) }));
-        await enqueueOperation(() => invoke('set_extras', { username: usernameValue, surname: surnameValue, wing: modelExtrasValue }));
-    } finally {
-        createBtn.disabled = false;
+        const created = await enqueueOperation(() => invoke('new_character', { username: usernameValue, surname: surnameValue || null }));
+        if (created.status === 'already_exists') {
+            alert(`A character named ${created.file_name} already exists!`);
+            return;
+        }
+        await enqueueOperation(() => invoke('apply_appearance', {
+            id: created.id,
+            changes: {
+                gender_race: Number(genderRaceValue),
+                hair_type: hairTypeValue,
+                hair_color: Number(hairColorValue),
+                eye_color: Number(eyeColorValue),
+                skintone: skintoneValue,
+                facepaint: facePaintValue,
+                extras: modelExtrasValue
+            }
+        }));
         alert('Character created with success!');
         invoke('open_explorer');
+    } catch (error) {
+        alert(describeError(error));
+    } finally {
+        createBtn.disabled = false;
     }
 });